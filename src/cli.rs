@@ -1,8 +1,50 @@
-use clap::{Args, Parser, Subcommand};
+use clap::{ArgAction, Args, Parser, Subcommand};
+use clap_complete::aot::Shell;
+use clap_complete::engine::ArgValueCompleter;
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::client::ClientOptions;
+use crate::completers;
+use crate::config::Profile;
+use crate::output::OutputFormat;
 
 #[derive(Parser, Debug)]
 #[command(name = "canine", version, about = "Canine CLI - Manage your Canine projects, clusters, and local development environment")]
 pub struct Cli {
+    /// Output format for list and show commands
+    #[arg(long, short, global = true, value_enum, default_value_t = OutputFormat::Table)]
+    pub output: OutputFormat,
+
+    /// Configuration profile to use
+    #[arg(long, global = true, env = "CANINE_PROFILE")]
+    pub profile: Option<String>,
+
+    /// API host, overriding the profile
+    #[arg(long, global = true, env = "CANINE_HOST")]
+    pub host: Option<String>,
+
+    /// API token, overriding the profile
+    #[arg(long, global = true, env = "CANINE_TOKEN", hide_env_values = true)]
+    pub token: Option<String>,
+
+    /// Account slug, overriding the profile
+    #[arg(long, global = true, env = "CANINE_ACCOUNT")]
+    pub account: Option<String>,
+
+    /// Seconds to wait for a connection to the API
+    #[arg(long, global = true, env = "CANINE_CONNECT_TIMEOUT", default_value_t = 10)]
+    pub connect_timeout: u64,
+
+    /// Seconds to wait for an API response
+    #[arg(long, global = true, env = "CANINE_READ_TIMEOUT", default_value_t = 30)]
+    pub read_timeout: u64,
+
+    /// Retries for API requests that fail with a transient error
+    #[arg(long, global = true, env = "CANINE_RETRIES", default_value_t = 3)]
+    pub retries: u32,
+
     #[command(subcommand)]
     pub namespace: Namespace,
 }
@@ -15,6 +57,9 @@ pub enum Namespace {
     /// Switch between Canine accounts
     Accounts(AccountCmd),
 
+    /// Manage configuration profiles for multiple hosts and tokens
+    Config(ConfigCmd),
+
     /// Manage projects (list, deploy, run commands)
     Projects(ProjectCmd),
 
@@ -29,6 +74,81 @@ pub enum Namespace {
 
     /// Run Canine locally with Docker Compose
     Local(LocalCmd),
+
+    /// Print a shell completion script
+    Completion(CompletionCmd),
+
+    /// Generate man pages
+    Man(ManCmd),
+
+    /// Create or update a project from a definition kept in the repository
+    Apply(ApplyCmd),
+
+    /// Create a .canine.yaml so commands in this directory default to a project
+    Init(InitCmd),
+
+    /// Check kubeconfig files
+    Kubeconfig(KubeconfigCmd),
+}
+
+#[derive(Args, Debug)]
+pub struct ApplyCmd {
+    /// Project definition to apply
+    #[arg(long, short, default_value = "canine.yaml")]
+    pub file: PathBuf,
+
+    /// Show what would change without changing it
+    #[arg(long, default_value_t = false)]
+    pub dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct KubeconfigCmd {
+    #[command(subcommand)]
+    pub action: KubeconfigAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum KubeconfigAction {
+    /// Report dangling references, duplicate names, conflicting TLS settings and unreadable files
+    Lint(KubeconfigLint),
+}
+
+#[derive(Args, Debug)]
+pub struct KubeconfigLint {
+    /// Kubeconfig to check, defaults to the first $KUBECONFIG entry or ~/.kube/config
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct InitCmd {
+    /// Project to use instead of choosing one interactively
+    #[arg(long, add = ArgValueCompleter::new(completers::projects))]
+    pub project: Option<String>,
+
+    /// Overwrite an existing .canine.yaml
+    #[arg(long, default_value_t = false)]
+    pub force: bool,
+}
+
+impl Cli {
+    /// Credentials passed as flags or through `CANINE_HOST`, `CANINE_TOKEN` and `CANINE_ACCOUNT`.
+    pub fn overrides(&self) -> Profile {
+        Profile {
+            host: self.host.clone(),
+            token: self.token.clone(),
+            token_ref: None,
+            account: self.account.clone(),
+        }
+    }
+
+    pub fn client_options(&self) -> ClientOptions {
+        let mut options = ClientOptions::default();
+        options.connect_timeout = Duration::from_secs(self.connect_timeout);
+        options.read_timeout = Duration::from_secs(self.read_timeout);
+        options.max_retries = self.retries;
+        options
+    }
 }
 
 // Build commands
@@ -46,6 +166,9 @@ pub enum BuildAction {
 
     /// Kill a specific build
     Kill(BuildId),
+
+    /// Print the output of a build
+    Logs(BuildLogsArgs),
 }
 
 // Auth commands
@@ -68,19 +191,37 @@ pub enum AuthAction {
     Logout,
 }
 
+/// Login reads `--token`, `--host` and `--account` from the global flags. Without a
+/// token it starts a device authorization in the browser.
 #[derive(Args, Debug)]
 pub struct AuthLogin {
-    /// API token for authentication
-    #[arg(long)]
-    pub token: String,
+    /// Read the API token from stdin
+    #[arg(long, default_value_t = false)]
+    pub with_token: bool,
+}
 
-    /// Custom API host
-    #[arg(long)]
-    pub host: Option<String>,
+// Config commands
+#[derive(Args, Debug)]
+pub struct ConfigCmd {
+    #[command(subcommand)]
+    pub action: ConfigAction,
+}
 
-    /// Account slug to use
-    #[arg(long)]
-    pub account: Option<String>,
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    /// Set the profile used by default
+    UseProfile(ProfileName),
+
+    /// List configured profiles
+    GetProfiles,
+
+    /// Delete a profile and its credentials
+    DeleteProfile(ProfileName),
+}
+
+#[derive(Args, Debug)]
+pub struct ProfileName {
+    pub name: String,
 }
 
 // Account commands
@@ -103,12 +244,93 @@ pub struct AccountId {
 
 #[derive(Args, Debug)]
 pub struct BuildList {
+    #[arg(add = ArgValueCompleter::new(completers::projects))]
     pub project: Option<String>,
+
+    #[command(flatten)]
+    pub list: ListOptions,
+
+    #[command(flatten)]
+    pub pages: PageOptions,
+
+    #[command(flatten)]
+    pub watch: WatchOptions,
+}
+
+/// Filtering, sorting and column selection, shared by list commands.
+#[derive(Args, Debug)]
+pub struct ListOptions {
+    /// Only show rows where a column has the given value (e.g. status=running,cluster=prod)
+    #[arg(long, value_delimiter = ',', value_parser = parse_assignment)]
+    pub filter: Vec<(String, String)>,
+
+    /// Column to sort rows by
+    #[arg(long)]
+    pub sort_by: Option<String>,
+
+    /// Sort in descending order
+    #[arg(long, default_value_t = false, requires = "sort_by")]
+    pub desc: bool,
+
+    /// Columns to show in tables, in order (e.g. id,name,status)
+    #[arg(long, value_delimiter = ',')]
+    pub columns: Vec<String>,
+
+    /// Show at most this many rows
+    #[arg(long)]
+    pub limit: Option<usize>,
+}
+
+/// Pagination for list commands whose endpoint returns pages.
+#[derive(Args, Debug)]
+pub struct PageOptions {
+    /// Page to fetch, starting at 1
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..), conflicts_with = "all")]
+    pub page: Option<u32>,
+
+    /// Number of items per page
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub per_page: Option<u32>,
+
+    /// Fetch every page
+    #[arg(long, default_value_t = false)]
+    pub all: bool,
+}
+
+/// `--watch` and friends, shared by list commands.
+#[derive(Args, Debug)]
+pub struct WatchOptions {
+    /// Keep refreshing the list, highlighting rows whose status changed
+    #[arg(long, short, default_value_t = false)]
+    pub watch: bool,
+
+    /// Seconds between refreshes
+    #[arg(long, default_value_t = 2, value_parser = clap::value_parser!(u64).range(1..), requires = "watch")]
+    pub interval: u64,
+
+    /// Stop watching once every row has one of these statuses (e.g. installed,failed)
+    #[arg(long, value_delimiter = ',', requires = "watch")]
+    pub until: Vec<String>,
 }
 
 #[derive(Args, Debug)]
 pub struct BuildId {
-    pub build: String,
+    #[arg(add = ArgValueCompleter::new(completers::builds))]
+    pub build: i32,
+}
+
+#[derive(Args, Debug)]
+pub struct BuildLogsArgs {
+    #[arg(add = ArgValueCompleter::new(completers::builds))]
+    pub build: i32,
+
+    /// Keep streaming output until the build finishes
+    #[arg(long, short, default_value_t = false)]
+    pub follow: bool,
+
+    /// Seconds to wait for the build to finish when using --follow
+    #[arg(long, default_value_t = 1800, requires = "follow")]
+    pub timeout: u64,
 }
 
 // Project commands
@@ -121,7 +343,16 @@ pub struct ProjectCmd {
 #[derive(Subcommand, Debug)]
 pub enum ProjectAction {
     /// List projects
-    List,
+    List(ProjectList),
+
+    /// Create a project
+    Create(ProjectCreate),
+
+    /// Change the settings of a project
+    Update(ProjectUpdate),
+
+    /// Delete a project
+    Delete(ProjectDelete),
 
     /// Run a command in a project
     Run(ProjectRun),
@@ -130,32 +361,408 @@ pub enum ProjectAction {
     Deploy(DeployProjectParams),
 
     /// List processes for a project
-    Processes(ProjectId),
+    Processes(ProjectProcesses),
+
+    /// Stream application logs for a project
+    Logs(ProjectLogs),
+
+    /// Manage one-off pods started by `projects run`
+    #[command(subcommand)]
+    OneOffs(OneOffAction),
+
+    /// View and change environment variables of a project
+    #[command(subcommand)]
+    Env(EnvAction),
+
+    /// Change the number of replicas of project processes, e.g. `web=3 worker=2`
+    Scale(ProjectScale),
+
+    /// Restart the processes of a project
+    Restart(ProjectRestart),
+
+    /// Show the deployment history of a project
+    #[command(subcommand)]
+    Deployments(DeploymentAction),
+
+    /// Redeploy an earlier build without rebuilding it
+    Rollback(ProjectRollback),
+}
+
+#[derive(Subcommand, Debug)]
+pub enum DeploymentAction {
+    /// List deployments, newest first
+    List(ProjectId),
 }
 
 #[derive(Args, Debug)]
-pub struct ProjectId {
+pub struct ProjectRollback {
+    #[arg(long, add = ArgValueCompleter::new(completers::projects))]
+    pub project: String,
+
+    /// Deployment to go back to. Defaults to the last successful deployment
+    /// of a build other than the current one
     #[arg(long)]
+    pub to: Option<i32>,
+
+    /// Wait until the rollback has rolled out
+    #[arg(long, default_value_t = false)]
+    pub wait: bool,
+
+    /// Seconds to wait for the rollout when using --wait
+    #[arg(long, default_value_t = 600, requires = "wait")]
+    pub timeout: u64,
+}
+
+#[derive(Args, Debug)]
+pub struct ProjectScale {
+    #[arg(long, add = ArgValueCompleter::new(completers::projects))]
     pub project: String,
+
+    /// Replicas as PROCESS=COUNT
+    #[arg(required = true, value_parser = parse_replicas)]
+    pub processes: Vec<(String, u32)>,
+
+    #[command(flatten)]
+    pub rollout: RolloutWait,
 }
 
 #[derive(Args, Debug)]
-pub struct ProjectRun {
+pub struct ProjectRestart {
+    #[arg(long, add = ArgValueCompleter::new(completers::projects))]
+    pub project: String,
+
+    /// Only restart this process, e.g. `web`
+    #[arg(long)]
+    pub process: Option<String>,
+
+    #[command(flatten)]
+    pub rollout: RolloutWait,
+}
+
+#[derive(Args, Debug)]
+pub struct RolloutWait {
+    /// Return as soon as the change is accepted instead of waiting for the rollout
+    #[arg(long, default_value_t = false)]
+    pub no_wait: bool,
+
+    /// Seconds to wait for the rollout to settle
+    #[arg(long, default_value_t = 600, conflicts_with = "no_wait")]
+    pub timeout: u64,
+}
+
+fn parse_replicas(s: &str) -> Result<(String, u32), String> {
+    let (process, count) = parse_assignment(s)?;
+    let count = count
+        .parse()
+        .map_err(|_| format!("expected a replica count for {}, got `{}`", process, count))?;
+    Ok((process, count))
+}
+
+#[derive(Args, Debug)]
+pub struct ProjectCreate {
+    #[arg(long)]
+    pub name: String,
+
+    /// Git repository to build from, e.g. `https://github.com/acme/api`
+    #[arg(long)]
+    pub repository_url: String,
+
+    /// Cluster to deploy to
+    #[arg(long, add = ArgValueCompleter::new(completers::clusters))]
+    pub cluster: String,
+
+    #[command(flatten)]
+    pub settings: ProjectSettings,
+}
+
+#[derive(Args, Debug)]
+pub struct ProjectUpdate {
+    #[arg(long, add = ArgValueCompleter::new(completers::projects))]
+    pub project: String,
+
+    /// Git repository to build from
+    #[arg(long)]
+    pub repository_url: Option<String>,
+
+    #[command(flatten)]
+    pub settings: ProjectSettings,
+}
+
+/// Build settings shared by `projects create` and `projects update`.
+#[derive(Args, Debug)]
+pub struct ProjectSettings {
+    /// Branch to build and deploy
+    #[arg(long)]
+    pub branch: Option<String>,
+
+    /// Path of the Dockerfile within the repository
+    #[arg(long)]
+    pub dockerfile_path: Option<String>,
+
+    /// Directory used as the Docker build context
+    #[arg(long)]
+    pub build_context: Option<String>,
+
+    /// Deploy automatically on every push to the branch
     #[arg(long)]
+    pub autodeploy: Option<bool>,
+}
+
+#[derive(Args, Debug)]
+pub struct ProjectDelete {
+    #[arg(long, add = ArgValueCompleter::new(completers::projects))]
+    pub project: String,
+
+    /// Delete without asking for confirmation
+    #[arg(long, short, default_value_t = false)]
+    pub yes: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum EnvAction {
+    /// List environment variables
+    List(EnvList),
+
+    /// Print the value of one environment variable
+    Get(EnvGet),
+
+    /// Set environment variables, e.g. `RAILS_ENV=production`
+    Set(EnvSet),
+
+    /// Remove environment variables
+    Unset(EnvUnset),
+
+    /// Set environment variables from a dotenv file
+    Import(EnvImport),
+
+    /// Write environment variables as a dotenv file
+    Export(EnvExport),
+}
+
+#[derive(Args, Debug)]
+pub struct EnvList {
+    #[arg(long, add = ArgValueCompleter::new(completers::projects))]
+    pub project: String,
+
+    /// Show secret values instead of masking them
+    #[arg(long, default_value_t = false)]
+    pub reveal: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct EnvGet {
+    #[arg(long, add = ArgValueCompleter::new(completers::projects))]
+    pub project: String,
+
+    pub name: String,
+
+    /// Show the value even if it is a secret
+    #[arg(long, default_value_t = false)]
+    pub reveal: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct EnvSet {
+    #[arg(long, add = ArgValueCompleter::new(completers::projects))]
+    pub project: String,
+
+    /// Variables as NAME=VALUE
+    #[arg(required = true, value_parser = parse_assignment)]
+    pub variables: Vec<(String, String)>,
+
+    /// Store the values as secrets
+    #[arg(long, default_value_t = false)]
+    pub secret: bool,
+
+    /// Redeploy the project so running processes pick up the change
+    #[arg(long, default_value_t = false)]
+    pub redeploy: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct EnvUnset {
+    #[arg(long, add = ArgValueCompleter::new(completers::projects))]
     pub project: String,
 
+    #[arg(required = true)]
+    pub names: Vec<String>,
+
+    /// Redeploy the project so running processes pick up the change
+    #[arg(long, default_value_t = false)]
+    pub redeploy: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct EnvImport {
+    #[arg(long, add = ArgValueCompleter::new(completers::projects))]
+    pub project: String,
+
+    /// Dotenv file to read, `-` for stdin
+    #[arg(long, short, default_value = ".env")]
+    pub file: PathBuf,
+
+    /// Store the values as secrets
+    #[arg(long, default_value_t = false)]
+    pub secret: bool,
+
+    /// Redeploy the project so running processes pick up the change
+    #[arg(long, default_value_t = false)]
+    pub redeploy: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct EnvExport {
+    #[arg(long, add = ArgValueCompleter::new(completers::projects))]
+    pub project: String,
+
+    /// File to write instead of stdout
+    #[arg(long, short)]
+    pub file: Option<PathBuf>,
+
+    /// Include secret values, which are left out otherwise
+    #[arg(long, default_value_t = false)]
+    pub reveal: bool,
+}
+
+fn parse_assignment(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((name, value)) if !name.is_empty() => Ok((name.to_string(), value.to_string())),
+        _ => Err(format!("expected NAME=VALUE, got `{}`", s)),
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum OneOffAction {
+    /// List one-off pods of a project
+    List(ProjectId),
+
+    /// Delete finished one-off pods left behind by earlier sessions
+    Prune(OneOffPrune),
+}
+
+#[derive(Args, Debug)]
+pub struct OneOffPrune {
+    #[arg(long, add = ArgValueCompleter::new(completers::projects))]
+    pub project: String,
+
+    /// Also delete pods that are still pending or running, ending sessions in use
+    #[arg(long, default_value_t = false)]
+    pub running: bool,
+
+    /// Delete without asking for confirmation
+    #[arg(long, short, default_value_t = false)]
+    pub yes: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ProjectId {
+    #[arg(long, add = ArgValueCompleter::new(completers::projects))]
+    pub project: String,
+}
+
+#[derive(Args, Debug)]
+pub struct ProjectList {
+    #[command(flatten)]
+    pub list: ListOptions,
+
+    #[command(flatten)]
+    pub pages: PageOptions,
+}
+
+#[derive(Args, Debug)]
+pub struct ProjectProcesses {
+    #[arg(long, add = ArgValueCompleter::new(completers::projects))]
+    pub project: String,
+
+    #[command(flatten)]
+    pub list: ListOptions,
+
+    #[command(flatten)]
+    pub watch: WatchOptions,
+}
+
+#[derive(Args, Debug)]
+pub struct ProjectRun {
+    /// Defaults to the project in .canine.yaml, or the one built from this repository
+    #[arg(long, add = ArgValueCompleter::new(completers::projects))]
+    pub project: Option<String>,
+
+    /// Attach stdin to the command. Use --interactive=false when there is no input to send
+    #[arg(
+        long,
+        short,
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        require_equals = true,
+        default_value_t = true,
+        default_missing_value = "true"
+    )]
+    pub interactive: bool,
+
+    /// Never allocate a TTY, even when stdin and stdout are terminals
+    #[arg(long, default_value_t = false)]
+    pub no_tty: bool,
+
+    /// Leave the one-off pod running after the command exits
+    #[arg(long, default_value_t = false)]
+    pub keep: bool,
+
+    /// Seconds to wait for the one-off pod to start, including the image pull
+    #[arg(long, default_value_t = 300)]
+    pub ready_timeout: u64,
+
+    /// Milliseconds between pod status checks while waiting for it to start
+    #[arg(long, default_value_t = 1000)]
+    pub poll_interval: u64,
+
     /// Command to run (e.g., "bundle exec rails c")
     #[arg(trailing_var_arg = true, required = true)]
     pub command: Vec<String>,
 }
 
 #[derive(Args, Debug)]
-pub struct DeployProjectParams {
+pub struct ProjectLogs {
+    /// Defaults to the project in .canine.yaml, or the one built from this repository
+    #[arg(long, add = ArgValueCompleter::new(completers::projects))]
+    pub project: Option<String>,
+
+    /// Only show pods belonging to this process (e.g., "web")
     #[arg(long)]
-    pub name: String,
+    pub process: Option<String>,
+
+    /// Only show logs newer than a relative duration (e.g., 5s, 2m, 3h)
+    #[arg(long)]
+    pub since: Option<String>,
+
+    /// Number of recent lines to show from each pod
+    #[arg(long)]
+    pub tail: Option<i64>,
+
+    /// Keep streaming new log lines
+    #[arg(long, short, default_value_t = false)]
+    pub follow: bool,
+
+    /// Show logs from the previous container instance
+    #[arg(long, default_value_t = false)]
+    pub previous: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct DeployProjectParams {
+    /// Defaults to the project in .canine.yaml, or the one built from this repository
+    #[arg(long, add = ArgValueCompleter::new(completers::projects))]
+    pub name: Option<String>,
 
     #[arg(long, default_value_t = false)]
     pub skip_build: bool,
+
+    /// Stream the build log and wait until the deployment finishes
+    #[arg(long, default_value_t = false)]
+    pub wait: bool,
+
+    /// Seconds to wait for the build and rollout when using --wait
+    #[arg(long, default_value_t = 1800, requires = "wait")]
+    pub timeout: u64,
 }
 
 // Cluster commands
@@ -174,9 +781,9 @@ pub struct AddOnCmd {
 #[derive(Subcommand, Debug)]
 pub enum ClusterAction {
     /// List clusters
-    List,
+    List(ClusterList),
     /// Download kubeconfig file
-    DownloadKubeconfig(ClusterId),
+    DownloadKubeconfig(DownloadKubeconfigParams),
 
     // Connect to cluster via telepresence
     Connect(ClusterId)
@@ -185,20 +792,66 @@ pub enum ClusterAction {
 #[derive(Subcommand, Debug)]
 pub enum AddOnAction {
     /// List add ons
-    List,
+    List(AddOnList),
     /// Download kubeconfig file
     Restart(AddOnId),
 }
 
+#[derive(Args, Debug)]
+pub struct ClusterList {
+    #[command(flatten)]
+    pub list: ListOptions,
+
+    #[command(flatten)]
+    pub pages: PageOptions,
+
+    #[command(flatten)]
+    pub watch: WatchOptions,
+}
+
+#[derive(Args, Debug)]
+pub struct AddOnList {
+    #[command(flatten)]
+    pub list: ListOptions,
+
+    #[command(flatten)]
+    pub pages: PageOptions,
+
+    #[command(flatten)]
+    pub watch: WatchOptions,
+}
+
 #[derive(Args, Debug)]
 pub struct ClusterId {
-    #[arg(long)]
+    #[arg(long, add = ArgValueCompleter::new(completers::clusters))]
     pub cluster: String,
 }
 
+#[derive(Args, Debug)]
+pub struct DownloadKubeconfigParams {
+    #[arg(long, add = ArgValueCompleter::new(completers::clusters))]
+    pub cluster: String,
+
+    /// Merge into $KUBECONFIG or ~/.kube/config as canine-<account>-<cluster>
+    #[arg(long, default_value_t = false, conflicts_with = "per_cluster")]
+    pub merge: bool,
+
+    /// Make the merged context the current context
+    #[arg(long, default_value_t = false, requires = "merge")]
+    pub set_context: bool,
+
+    /// Write to ~/.k9/clusters/canine-<account>-<cluster>.yaml instead of a shared file
+    #[arg(long, default_value_t = false)]
+    pub per_cluster: bool,
+
+    /// File to write or merge into
+    #[arg(long, conflicts_with = "per_cluster")]
+    pub path: Option<PathBuf>,
+}
+
 #[derive(Args, Debug)]
 pub struct AddOnId {
-    #[arg(long)]
+    #[arg(long, add = ArgValueCompleter::new(completers::add_ons))]
     pub add_on: String,
 }
 
@@ -227,3 +880,20 @@ pub enum LocalAction {
     /// Upgrade local Canine environment
     Upgrade,
 }
+
+// Completion commands
+#[derive(Args, Debug)]
+pub struct CompletionCmd {
+    pub shell: Shell,
+
+    /// Print a static script that does not complete project, cluster, build or add-on names
+    #[arg(long = "static", default_value_t = false)]
+    pub static_script: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ManCmd {
+    /// Write a page for every subcommand into this directory instead of printing `canine.1`
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+}
@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use url::Url;
+
+use super::{Auth, CanineClient, CanineError, ClientOptions, DEFAULT_HOST};
+
+/// Configures a [`CanineClient`]. Everything is optional: without a host the
+/// client talks to canine.sh, and without credentials it only reaches the
+/// endpoints that work before login.
+#[derive(Debug, Default)]
+pub struct ClientBuilder {
+    host: Option<String>,
+    auth: Option<Auth>,
+    account: Option<String>,
+    options: ClientOptions,
+    http: Option<reqwest::Client>,
+}
+
+impl ClientBuilder {
+    /// Base URL of the Canine server, e.g. `https://canine.example.com`.
+    pub fn host(mut self, host: impl Into<String>) -> Self {
+        self.host = Some(host.into());
+        self
+    }
+
+    /// How requests authenticate, [`Auth::None`] by default.
+    pub fn auth(mut self, auth: Auth) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// Authenticates with an API token, as created in the dashboard or by `canine auth login`.
+    pub fn api_key(self, token: impl Into<String>) -> Self {
+        self.auth(Auth::ApiKey(token.into()))
+    }
+
+    /// Authenticates with an OAuth access token.
+    pub fn bearer_token(self, token: impl Into<String>) -> Self {
+        self.auth(Auth::Bearer(token.into()))
+    }
+
+    /// Account to act in, for users that belong to more than one.
+    pub fn account(mut self, account: impl Into<String>) -> Self {
+        self.account = Some(account.into());
+        self
+    }
+
+    /// Time allowed to establish a connection, 10 seconds by default.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.options.connect_timeout = timeout;
+        self
+    }
+
+    /// Time allowed between bytes of a response, 30 seconds by default.
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.options.read_timeout = timeout;
+        self
+    }
+
+    /// Retries for reads (GET, HEAD, OPTIONS) that fail with 5xx, 429 or a dropped connection.
+    pub fn max_retries(mut self, retries: u32) -> Self {
+        self.options.max_retries = retries;
+        self
+    }
+
+    /// Delay before the first retry, doubled on every attempt.
+    pub fn retry_backoff(mut self, backoff: Duration) -> Self {
+        self.options.retry_backoff = backoff;
+        self
+    }
+
+    /// Replaces the timeouts and retry settings at once.
+    pub fn options(mut self, options: ClientOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Sends requests through `http`, for proxies, custom certificates or a
+    /// shared connection pool. Its own timeouts apply instead of the ones set here.
+    pub fn http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = Some(http);
+        self
+    }
+
+    /// Creates the client, failing when the host is not a valid URL.
+    pub fn build(self) -> Result<CanineClient, CanineError> {
+        let base_url = Url::parse(self.host.as_deref().unwrap_or(DEFAULT_HOST))?;
+        let http = match self.http {
+            Some(http) => http,
+            None => reqwest::Client::builder()
+                .connect_timeout(self.options.connect_timeout)
+                .read_timeout(self.options.read_timeout)
+                .build()?,
+        };
+
+        Ok(CanineClient {
+            http,
+            base_url,
+            auth: self.auth.unwrap_or(Auth::None),
+            account: self.account,
+            options: self.options,
+        })
+    }
+}
@@ -1,32 +1,147 @@
 use reqwest::StatusCode;
+use serde::Deserialize;
 use thiserror::Error;
 
+/// An error response from the API.
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum ApiError {
-    #[error("unauthorized (401)")]
-    Unauthorized,
-    #[error("forbidden (403)")]
-    Forbidden,
-    #[error("not found (404)")]
-    NotFound,
-    #[error("server error ({status})")]
-    ServerError { status: StatusCode, body: String },
+    /// The token is missing, expired or revoked.
+    #[error("unauthorized (401){}, token expired or invalid, run `canine auth login`", detail(message))]
+    Unauthorized {
+        /// Message from the response body, empty if there is none.
+        message: String,
+    },
+    /// The token is valid but may not act on this account or resource.
+    #[error("forbidden (403){}, check the account with `canine auth status`", detail(message))]
+    Forbidden {
+        /// Message from the response body, empty if there is none.
+        message: String,
+    },
+    /// The resource does not exist, or is not visible to this account.
+    #[error("not found (404){}", detail(message))]
+    NotFound {
+        /// Message from the response body, empty if there is none.
+        message: String,
+    },
+    /// Too many requests; retried automatically for reads.
+    #[error("rate limited (429){}, try again in a moment", detail(message))]
+    RateLimited {
+        /// Message from the response body, empty if there is none.
+        message: String,
+    },
+    /// Any other 4xx, such as a 400 or 422 validation error, with the raw response body.
+    #[error("request failed ({status}){}", detail(&error_message(body)))]
+    ClientError {
+        /// The HTTP status.
+        status: StatusCode,
+        /// The response body as sent.
+        body: String,
+    },
+    /// A 5xx or any other status, with the raw response body.
+    #[error("server error ({status}){}", detail(&error_message(body)))]
+    ServerError {
+        /// The HTTP status.
+        status: StatusCode,
+        /// The response body as sent.
+        body: String,
+    },
 }
 
+impl ApiError {
+    /// Maps an unsuccessful response to its variant.
+    pub fn from_response(status: StatusCode, body: String) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED => ApiError::Unauthorized { message: error_message(&body) },
+            StatusCode::FORBIDDEN => ApiError::Forbidden { message: error_message(&body) },
+            StatusCode::NOT_FOUND => ApiError::NotFound { message: error_message(&body) },
+            StatusCode::TOO_MANY_REQUESTS => ApiError::RateLimited { message: error_message(&body) },
+            _ if status.is_client_error() => ApiError::ClientError { status, body },
+            _ => ApiError::ServerError { status, body },
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ErrorBody {
+    Error { error: String },
+    Message { message: String },
+    Errors { errors: Vec<String> },
+}
+
+/// Pulls the human readable message out of a JSON error body, falling back to the raw body.
+fn error_message(body: &str) -> String {
+    match serde_json::from_str::<ErrorBody>(body) {
+        Ok(ErrorBody::Error { error }) => error,
+        Ok(ErrorBody::Message { message }) => message,
+        Ok(ErrorBody::Errors { errors }) => errors.join(", "),
+        Err(_) if body.trim_start().starts_with('<') => String::new(),
+        Err(_) => body.trim().to_string(),
+    }
+}
+
+fn detail(message: &str) -> String {
+    if message.is_empty() {
+        String::new()
+    } else {
+        format!(": {}", message)
+    }
+}
+
+/// Everything that can go wrong talking to Canine.
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum CanineError {
+    /// The user is not a member of the named account.
     #[error("account not found: {0}")]
     NoAccount(String),
-    #[error("no token configured")]
+    /// No API token was configured.
+    #[error("no token configured, run `canine auth login` or set CANINE_TOKEN")]
     NoToken,
-    #[error("one-off pod never became ready")]
-    OneOffPodNeverReady,
+    /// The user rejected a device authorization.
+    #[error("login was denied in the browser")]
+    DeviceAuthorizationDenied,
+    /// The device code expired before it was approved.
+    #[error("login code expired, run `canine auth login` again")]
+    DeviceAuthorizationExpired,
+    /// A one-off pod did not start in time.
+    #[error("one-off pod {pod} was not ready after {seconds}s, last status: {status}")]
+    OneOffPodTimedOut {
+        /// Name of the pod.
+        pod: String,
+        /// How long it was waited for.
+        seconds: u64,
+        /// The last status seen, with its reason.
+        status: String,
+    },
+    /// A one-off pod failed or exited before it was ready.
+    #[error("one-off pod {pod} stopped before it was ready: {status}")]
+    OneOffPodStopped {
+        /// Name of the pod.
+        pod: String,
+        /// The status it stopped with, with its reason.
+        status: String,
+    },
+    /// The API answered with an error status.
     #[error("api error: {0}")]
     Api(#[from] ApiError),
+    /// A path could not be joined onto the host.
     #[error("url join error: {0}")]
     UrlJoin(String),
+    /// A `Link` header pointed the next page at another host.
+    #[error("refusing to follow a next page link to another host: {0}")]
+    ForeignNextLink(String),
+    /// The server pointed at a page it had already sent.
+    #[error("the server sent the same next page twice, stopping")]
+    PaginationLoop,
+    /// The configured host is not a valid URL.
+    #[error("invalid host: {0}")]
+    InvalidHost(#[from] url::ParseError),
+    /// The request could not be sent or the response not read.
     #[error(transparent)]
     Transport(#[from] reqwest::Error),
+    /// The response was not the JSON expected.
     #[error(transparent)]
     Json(#[from] serde_json::Error),
 }
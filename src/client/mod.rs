@@ -1,38 +1,103 @@
+//! Client for the Canine REST API and the types it exchanges.
+
+mod builder;
 mod error;
+mod pagination;
 mod types;
 
+pub use builder::ClientBuilder;
 pub use error::{ApiError, CanineError};
+pub use pagination::{ListRequest, Page, Paginated};
 pub use types::*;
 
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use percent_encoding::{AsciiSet, CONTROLS, utf8_percent_encode};
+use rand::Rng;
+use reqwest::header::HeaderMap;
+use reqwest::{Method, StatusCode};
 use serde::Serialize;
 use url::Url;
 
+/// The server used when no host is configured.
+pub const DEFAULT_HOST: &str = "https://canine.sh";
+
+/// A connection to the Canine API. Cheap to clone, clones share the
+/// connection pool. Create one with [`CanineClient::builder`].
 #[derive(Clone, Debug)]
 pub struct CanineClient {
     http: reqwest::Client,
+    /// The host requests are sent to.
     pub base_url: Url,
     auth: Auth,
     account: Option<String>,
+    options: ClientOptions,
+}
+
+/// Timeouts and retry behaviour for API requests. Start from
+/// [`ClientOptions::default`] and change the fields you need.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ClientOptions {
+    /// Time allowed to establish a connection.
+    pub connect_timeout: Duration,
+    /// Time allowed between bytes of a response.
+    pub read_timeout: Duration,
+    /// Retries for reads (GET, HEAD, OPTIONS) that fail with 5xx, 429 or a dropped connection.
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled on every attempt.
+    pub retry_backoff: Duration,
 }
 
+impl Default for ClientOptions {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(10),
+            read_timeout: Duration::from_secs(30),
+            max_retries: 3,
+            retry_backoff: Duration::from_millis(500),
+        }
+    }
+}
+
+const MAX_BACKOFF: Duration = Duration::from_secs(10);
+
+/// How requests authenticate.
 #[derive(Clone, Debug)]
+#[non_exhaustive]
 pub enum Auth {
+    /// An OAuth access token, sent as `Authorization: Bearer`.
     Bearer(String),
+    /// An API token, sent as `X-API-KEY`.
     ApiKey(String),
+    /// For the endpoints used before a token exists, like device authorization.
+    None,
 }
 
 impl CanineClient {
-    pub fn new(
+    /// Starts configuring a client.
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::default()
+    }
+
+    /// A client for `url` with the default timeouts and retries.
+    pub fn new(url: impl AsRef<str>, auth: Auth, account: Option<String>) -> Result<Self, CanineError> {
+        Self::with_options(url, auth, account, ClientOptions::default())
+    }
+
+    /// A client for `url` with the given timeouts and retries.
+    pub fn with_options(
         url: impl AsRef<str>,
         auth: Auth,
         account: Option<String>,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
-        Ok(Self {
-            base_url: Url::parse(url.as_ref())?,
-            auth,
-            http: reqwest::Client::new(),
-            account,
-        })
+        options: ClientOptions,
+    ) -> Result<Self, CanineError> {
+        let builder = Self::builder().host(url.as_ref()).auth(auth).options(options);
+        match account {
+            Some(account) => builder.account(account).build(),
+            None => builder.build(),
+        }
     }
 
     async fn send_request<RBody, T: Serialize>(
@@ -41,6 +106,20 @@ impl CanineClient {
         method: reqwest::Method,
         body: Option<&T>,
     ) -> Result<RBody, CanineError>
+    where
+        RBody: serde::de::DeserializeOwned,
+    {
+        Ok(self.send_request_with_headers(path, method, body).await?.0)
+    }
+
+    /// Like `send_request`, also returning the response headers, which carry
+    /// pagination links on some endpoints.
+    async fn send_request_with_headers<RBody, T: Serialize>(
+        &self,
+        path: &str,
+        method: reqwest::Method,
+        body: Option<&T>,
+    ) -> Result<(RBody, HeaderMap), CanineError>
     where
         RBody: serde::de::DeserializeOwned,
     {
@@ -49,57 +128,150 @@ impl CanineClient {
             .join(path)
             .map_err(|e| CanineError::UrlJoin(e.to_string()))?;
 
-        let mut req = self.http.request(method, url);
-        if let Some(account) = &self.account {
-            req = req.header("X-ACCOUNT-ID", account);
-        }
+        // Only reads are repeated. Writes such as restart and scale are PUTs
+        // but not safe to apply twice, and a connect error can still hide a
+        // request the server received.
+        let idempotent = matches!(method, Method::GET | Method::HEAD | Method::OPTIONS);
+
+        let mut attempt = 0;
+        loop {
+            let mut req = self.http.request(method.clone(), url.clone());
+            if let Some(account) = &self.account {
+                req = req.header("X-ACCOUNT-ID", account);
+            }
+
+            match &self.auth {
+                Auth::ApiKey(token) => req = req.header("X-API-KEY", token),
+                Auth::Bearer(token) => req = req.bearer_auth(token),
+                Auth::None => {}
+            }
+
+            if let Some(body) = body {
+                req = req.json(body);
+            }
+
+            let retries_left = attempt < self.options.max_retries;
+            let res = match req.send().await {
+                Ok(res) => res,
+                Err(e) if retries_left && idempotent && (e.is_connect() || is_transient(&e)) => {
+                    tokio::time::sleep(self.backoff(attempt, None)).await;
+                    attempt += 1;
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
 
-        if let Auth::ApiKey(token) = &self.auth {
-            req = req.header("X-API-KEY", token);
+            let status = res.status();
+            if retries_left
+                && idempotent
+                && (status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS)
+            {
+                let retry_after = res
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.parse().ok())
+                    .map(Duration::from_secs);
+                tokio::time::sleep(self.backoff(attempt, retry_after)).await;
+                attempt += 1;
+                continue;
+            }
+
+            let headers = res.headers().clone();
+            let body = res.text().await?;
+            return if status.is_success() {
+                // Endpoints without a response body (204, empty 200) deserialize as `()`.
+                let body = if body.trim().is_empty() { "null" } else { body.as_str() };
+                Ok((serde_json::from_str(body)?, headers))
+            } else {
+                Err(ApiError::from_response(status, body).into())
+            };
         }
+    }
 
-        if let Some(body) = body {
-            req = req.json(body);
+    /// Exponential backoff with jitter, unless the server asked for a specific delay.
+    fn backoff(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        if let Some(delay) = retry_after {
+            return delay.min(MAX_BACKOFF);
         }
+        let delay = self
+            .options
+            .retry_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(MAX_BACKOFF);
+        let jitter = rand::thread_rng().gen_range(0..=delay.as_millis() as u64 / 4);
+        delay + Duration::from_millis(jitter)
+    }
 
-        let res = req.send().await?;
-        let status = res.status();
+    /// Starts a browser login, returning the code the user has to confirm.
+    pub async fn start_device_authorization(&self) -> Result<DeviceAuthorization, CanineError> {
+        self.send_request::<DeviceAuthorization, ()>(
+            "/api/v1/device_authorizations",
+            reqwest::Method::POST,
+            None,
+        )
+        .await
+    }
 
-        let body = res.text().await?;
-        if status.is_success() {
-            let me: RBody = serde_json::from_str(&body)?;
-            Ok(me)
-        } else {
-            Err(ApiError::ServerError { status, body }.into())
-        }
+    /// Exchanges an approved device code for an API token. Fails with a 4xx
+    /// [`ApiError`] whose body is a [`DeviceTokenError`] until then.
+    pub async fn request_device_token(&self, device_code: &str) -> Result<DeviceToken, CanineError> {
+        self.send_request::<DeviceToken, DeviceTokenRequest>(
+            "/api/v1/device_authorizations/token",
+            reqwest::Method::POST,
+            Some(&DeviceTokenRequest {
+                device_code: device_code.to_string(),
+            }),
+        )
+        .await
     }
 
+    /// The user the token belongs to, along with their accounts.
     pub async fn me(&self) -> Result<User, CanineError> {
         self.send_request::<User, ()>("/api/v1/me", reqwest::Method::GET, None)
             .await
     }
 
+    /// The first page of projects. Use [`CanineClient::projects`] to page through all of them.
     pub async fn get_projects(&self) -> Result<ProjectsResponse, CanineError> {
         self.send_request::<ProjectsResponse, ()>("/api/v1/projects", reqwest::Method::GET, None)
             .await
     }
 
+    /// The first page of clusters. Use [`CanineClient::clusters`] to page through all of them.
     pub async fn get_clusters(&self) -> Result<ClustersResponse, CanineError> {
         self.send_request::<ClustersResponse, ()>("/api/v1/clusters", reqwest::Method::GET, None)
             .await
     }
 
-    pub async fn get_builds(&self, project_id: &Option<String>) -> Result<BuildsResponse, CanineError> {
-        if let Some(project_id) = project_id {
-            self.send_request::<BuildsResponse, ()>(format!("/api/v1/builds?project_id={}", project_id).as_str(), reqwest::Method::GET, None)
-                .await
-        } else {
-            self.send_request::<BuildsResponse, ()>("/api/v1/builds", reqwest::Method::GET, None)
-                .await
-        }
+    /// Lists builds, narrowed down by query parameters such as `project_id`.
+    pub async fn get_builds(&self, query: &[(String, String)]) -> Result<BuildsResponse, CanineError> {
+        self.send_request::<BuildsResponse, ()>(&with_query("/api/v1/builds", query), reqwest::Method::GET, None)
+            .await
+    }
+
+    /// A single build, including its deployment once it has one.
+    pub async fn get_build(&self, build_id: i32) -> Result<Build, CanineError> {
+        self.send_request::<Build, ()>(
+            format!("/api/v1/builds/{}", build_id).as_str(),
+            reqwest::Method::GET,
+            None,
+        )
+        .await
+    }
+
+    /// The log of a build so far.
+    pub async fn get_build_logs(&self, build_id: i32) -> Result<BuildLogs, CanineError> {
+        self.send_request::<BuildLogs, ()>(
+            format!("/api/v1/builds/{}/logs", build_id).as_str(),
+            reqwest::Method::GET,
+            None,
+        )
+        .await
     }
 
-    pub async fn kill_build(&self, build_id: &str) -> Result<(), CanineError> {
+    /// Stops a running build.
+    pub async fn kill_build(&self, build_id: i32) -> Result<(), CanineError> {
         self.send_request::<(), ()>(
             format!("/api/v1/builds/{}/kill", build_id).as_str(),
             reqwest::Method::PUT,
@@ -107,6 +279,7 @@ impl CanineClient {
         ).await
     }
 
+    /// A project by name or id.
     pub async fn get_project(&self, project_id: &str) -> Result<Project, CanineError> {
         self.send_request::<Project, ()>(
             format!("/api/v1/projects/{}", project_id).as_str(),
@@ -116,6 +289,37 @@ impl CanineClient {
         .await
     }
 
+    /// Creates a project from `spec`, which needs at least a name, repository and cluster.
+    pub async fn create_project(&self, spec: ProjectSpec) -> Result<Project, CanineError> {
+        self.send_request::<Project, ProjectRequest>(
+            "/api/v1/projects",
+            reqwest::Method::POST,
+            Some(&ProjectRequest { project: spec }),
+        )
+        .await
+    }
+
+    /// Changes the settings set in `spec`, leaving the others as they are.
+    pub async fn update_project(&self, project_id: &str, spec: ProjectSpec) -> Result<Project, CanineError> {
+        self.send_request::<Project, ProjectRequest>(
+            format!("/api/v1/projects/{}", project_id).as_str(),
+            reqwest::Method::PATCH,
+            Some(&ProjectRequest { project: spec }),
+        )
+        .await
+    }
+
+    /// Deletes a project along with its processes and deployment history.
+    pub async fn delete_project(&self, project_id: &str) -> Result<(), CanineError> {
+        self.send_request::<(), ()>(
+            format!("/api/v1/projects/{}", project_id).as_str(),
+            reqwest::Method::DELETE,
+            None,
+        )
+        .await
+    }
+
+    /// The pods running the processes of a project.
     pub async fn get_processes(&self, project_id: &str) -> Result<ProcessesResponse, CanineError> {
         self.send_request::<ProcessesResponse, ()>(
             format!("/api/v1/projects/{}/processes", project_id).as_str(),
@@ -125,6 +329,7 @@ impl CanineClient {
         .await
     }
 
+    /// Starts a pod for running a one-off command, as `projects run` does.
     pub async fn create_one_off_pod(&self, project_id: &str) -> Result<Pod, CanineError> {
         self.send_request::<Pod, ()>(
             format!("/api/v1/projects/{}/processes", project_id).as_str(),
@@ -134,6 +339,27 @@ impl CanineClient {
         .await
     }
 
+    /// Pods started with `projects run`, including ones left behind by earlier sessions.
+    pub async fn get_one_off_pods(&self, project_id: &str) -> Result<ProcessesResponse, CanineError> {
+        self.send_request::<ProcessesResponse, ()>(
+            format!("/api/v1/projects/{}/processes?one_off=true", project_id).as_str(),
+            reqwest::Method::GET,
+            None,
+        )
+        .await
+    }
+
+    /// Deletes a pod of a project.
+    pub async fn delete_pod(&self, project_id: &str, pod_id: &str) -> Result<(), CanineError> {
+        self.send_request::<(), ()>(
+            format!("/api/v1/projects/{}/processes/{}", project_id, pod_id).as_str(),
+            reqwest::Method::DELETE,
+            None,
+        )
+        .await
+    }
+
+    /// A single pod of a project.
     pub async fn get_pod(&self, project_id: &str, pod_id: &str) -> Result<Pod, CanineError> {
         self.send_request::<Pod, ()>(
             format!("/api/v1/projects/{}/processes/{}", project_id, pod_id).as_str(),
@@ -143,19 +369,96 @@ impl CanineClient {
         .await
     }
 
+    /// Environment variables of a project. Secret values are included.
+    pub async fn get_environment_variables(
+        &self,
+        project_id: &str,
+    ) -> Result<EnvironmentVariablesResponse, CanineError> {
+        self.send_request::<EnvironmentVariablesResponse, ()>(
+            format!("/api/v1/projects/{}/environment_variables", project_id).as_str(),
+            reqwest::Method::GET,
+            None,
+        )
+        .await
+    }
+
+    /// Creates or updates the given variables, leaving all others as they are.
+    pub async fn set_environment_variables(
+        &self,
+        project_id: &str,
+        variables: Vec<EnvironmentVariableUpdate>,
+    ) -> Result<EnvironmentVariablesResponse, CanineError> {
+        self.send_request::<EnvironmentVariablesResponse, UpdateEnvironmentVariablesRequest>(
+            format!("/api/v1/projects/{}/environment_variables", project_id).as_str(),
+            reqwest::Method::PUT,
+            Some(&UpdateEnvironmentVariablesRequest {
+                environment_variables: variables,
+            }),
+        )
+        .await
+    }
+
+    /// Removes an environment variable from a project.
+    pub async fn delete_environment_variable(&self, project_id: &str, name: &str) -> Result<(), CanineError> {
+        self.send_request::<(), ()>(
+            format!("/api/v1/projects/{}/environment_variables/{}", project_id, path_segment(name)).as_str(),
+            reqwest::Method::DELETE,
+            None,
+        )
+        .await
+    }
+
+    /// Sets the number of replicas of the given processes, e.g. `web` to 3.
+    pub async fn scale_project(
+        &self,
+        project_id: &str,
+        processes: BTreeMap<String, u32>,
+    ) -> Result<(), CanineError> {
+        self.send_request::<(), ScaleProjectRequest>(
+            format!("/api/v1/projects/{}/scale", project_id).as_str(),
+            reqwest::Method::PUT,
+            Some(&ScaleProjectRequest { processes }),
+        )
+        .await
+    }
+
+    /// Replaces the pods of `process`, or of every process without one.
+    pub async fn restart_project(&self, project_id: &str, process: Option<String>) -> Result<(), CanineError> {
+        self.send_request::<(), RestartProjectRequest>(
+            format!("/api/v1/projects/{}/restart", project_id).as_str(),
+            reqwest::Method::PUT,
+            Some(&RestartProjectRequest { process }),
+        )
+        .await
+    }
+
+    /// Builds and deploys the latest commit, or redeploys `build_id` without
+    /// building. With `skip_build` the latest build is deployed as is.
     pub async fn deploy_project(
         &self,
         project_id: &str,
         skip_build: bool,
+        build_id: Option<i32>,
     ) -> Result<DeployProjectResponse, CanineError> {
         self.send_request::<DeployProjectResponse, DeployProjectRequest>(
             format!("/api/v1/projects/{}/deploy", project_id).as_str(),
             reqwest::Method::POST,
-            Some(&DeployProjectRequest { skip_build }),
+            Some(&DeployProjectRequest { skip_build, build_id }),
         )
         .await
     }
 
+    /// Deployment history of a project, newest first.
+    pub async fn get_deployments(&self, project_id: &str) -> Result<DeploymentsResponse, CanineError> {
+        self.send_request::<DeploymentsResponse, ()>(
+            format!("/api/v1/projects/{}/deployments", project_id).as_str(),
+            reqwest::Method::GET,
+            None,
+        )
+        .await
+    }
+
+    /// Credentials for a cluster, with a token that can manage it.
     pub async fn download_kubeconfig_file(
         &self,
         cluster_id: &str,
@@ -168,13 +471,436 @@ impl CanineClient {
         .await
     }
 
+    /// The first page of add-ons. Use [`CanineClient::add_ons`] to page through all of them.
     pub async fn get_add_ons(&self) -> Result<AddOnsResponse, CanineError> {
         self.send_request::<AddOnsResponse, ()>("/api/v1/add_ons", reqwest::Method::GET, None)
             .await
     }
 
+    /// Restarts the pods of an add-on.
     pub async fn restart_add_on(&self, add_on_id: &str) -> Result<(), CanineError> {
         self.send_request::<(), ()>(format!("/api/v1/add_ons/{}/restart", add_on_id).as_str(), reqwest::Method::PUT, None)
             .await
     }
 }
+
+/// Characters that would end or change a path segment, per the URL standard.
+const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// Encodes `value` for use as a single path segment.
+fn path_segment(value: &str) -> String {
+    utf8_percent_encode(value, PATH_SEGMENT).to_string()
+}
+
+/// Appends `query` to `path`, encoding names and values.
+fn with_query(path: &str, query: &[(String, String)]) -> String {
+    if query.is_empty() {
+        return path.to_string();
+    }
+    let query = url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(query)
+        .finish();
+    format!("{}?{}", path, query)
+}
+
+/// Timeouts and dropped connections that are worth another attempt.
+fn is_transient(error: &reqwest::Error) -> bool {
+    if error.is_timeout() {
+        return true;
+    }
+    let mut source = std::error::Error::source(error);
+    while let Some(err) = source {
+        if let Some(io) = err.downcast_ref::<std::io::Error>() {
+            return matches!(
+                io.kind(),
+                std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::BrokenPipe
+                    | std::io::ErrorKind::UnexpectedEof
+            );
+        }
+        source = err.source();
+    }
+    false
+}
+
+/// Client for a wiremock server, with retries that do not slow tests down.
+#[cfg(test)]
+pub(crate) fn mock_client(server: &wiremock::MockServer) -> CanineClient {
+    CanineClient::builder()
+        .host(server.uri())
+        .api_key("token")
+        .retry_backoff(Duration::from_millis(1))
+        .build()
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{body_json, header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn maps_unauthorized_with_server_message() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/me"))
+            .respond_with(ResponseTemplate::new(401).set_body_json(json!({ "error": "Token has expired" })))
+            .mount(&server)
+            .await;
+
+        let err = mock_client(&server).me().await.unwrap_err();
+        assert!(matches!(
+            &err,
+            CanineError::Api(ApiError::Unauthorized { message }) if message == "Token has expired"
+        ));
+        assert!(err.to_string().contains("run `canine auth login`"));
+    }
+
+    #[tokio::test]
+    async fn maps_not_found() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/projects/missing"))
+            .respond_with(ResponseTemplate::new(404).set_body_json(json!({ "message": "Project not found" })))
+            .mount(&server)
+            .await;
+
+        let err = mock_client(&server).get_project("missing").await.unwrap_err();
+        assert!(matches!(err, CanineError::Api(ApiError::NotFound { .. })));
+    }
+
+    #[tokio::test]
+    async fn maps_validation_errors_to_client_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/projects/web"))
+            .respond_with(
+                ResponseTemplate::new(422).set_body_json(json!({ "errors": ["Name has already been taken"] })),
+            )
+            .mount(&server)
+            .await;
+
+        let err = mock_client(&server).get_project("web").await.unwrap_err();
+        assert!(matches!(err, CanineError::Api(ApiError::ClientError { .. })));
+        assert!(err.to_string().contains("request failed (422"));
+        assert!(err.to_string().contains("Name has already been taken"));
+    }
+
+    #[tokio::test]
+    async fn retries_idempotent_requests_on_server_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/clusters"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/clusters"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/clusters"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "clusters": [] })))
+            .mount(&server)
+            .await;
+
+        let clusters = mock_client(&server).get_clusters().await.unwrap();
+        assert!(clusters.clusters.is_empty());
+        assert_eq!(server.received_requests().await.unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_retries() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/clusters"))
+            .respond_with(ResponseTemplate::new(502))
+            .mount(&server)
+            .await;
+
+        let err = mock_client(&server).get_clusters().await.unwrap_err();
+        assert!(matches!(err, CanineError::Api(ApiError::ServerError { .. })));
+        assert_eq!(server.received_requests().await.unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_writes() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/projects/api/deploy"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/api/v1/projects/api/restart"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+
+        assert!(mock_client(&server).deploy_project("api", false, None).await.is_err());
+        assert!(mock_client(&server).restart_project("api", None).await.is_err());
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn creates_updates_and_deletes_projects() {
+        let server = MockServer::start().await;
+        let project = serde_json::json!({
+            "id": 2, "name": "web", "namespace": "web", "repository_url": "https://github.com/acme/web",
+            "branch": "main", "status": "creating", "cluster_id": 1, "cluster_name": "production",
+        });
+        Mock::given(method("POST"))
+            .and(path("/api/v1/projects"))
+            .and(body_json(serde_json::json!({ "project": {
+                "name": "web", "repository_url": "https://github.com/acme/web", "cluster_name": "production",
+            }})))
+            .respond_with(ResponseTemplate::new(201).set_body_json(&project))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/api/v1/projects/web"))
+            .and(body_json(serde_json::json!({ "project": { "branch": "develop" } })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&project))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/api/v1/projects/web"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = mock_client(&server);
+        let created = client
+            .create_project(ProjectSpec {
+                name: Some("web".to_string()),
+                repository_url: Some("https://github.com/acme/web".to_string()),
+                cluster_name: Some("production".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(created.name, "web");
+        client
+            .update_project("web", ProjectSpec { branch: Some("develop".to_string()), ..Default::default() })
+            .await
+            .unwrap();
+        client.delete_project("web").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn deploys_a_given_build() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/projects/api/deploy"))
+            .and(body_json(serde_json::json!({ "skip_build": true, "build_id": 20 })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "message": "Deploying build 20",
+                "build_id": 20,
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let result = mock_client(&server).deploy_project("api", true, Some(20)).await.unwrap();
+        assert_eq!(result.build_id, 20);
+    }
+
+    #[tokio::test]
+    async fn accepts_empty_response_bodies() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path("/api/v1/builds/1/kill"))
+            .respond_with(ResponseTemplate::new(204))
+            .mount(&server)
+            .await;
+
+        mock_client(&server).kill_build(1).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn builder_sets_credentials_and_account() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/me"))
+            .and(header("Authorization", "Bearer secret"))
+            .and(header("X-ACCOUNT-ID", "acme"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": 1, "email": "jane@example.com", "name": "Jane",
+                "current_account": { "id": 1, "slug": "acme" }, "accounts": []
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = CanineClient::builder()
+            .host(server.uri())
+            .bearer_token("secret")
+            .account("acme")
+            .http_client(reqwest::Client::new())
+            .build()
+            .unwrap();
+        client.me().await.unwrap();
+
+        assert_eq!(CanineClient::builder().build().unwrap().base_url.as_str(), "https://canine.sh/");
+        assert!(matches!(
+            CanineClient::builder().host("not a url").build(),
+            Err(CanineError::InvalidHost(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn lists_builds_with_query_parameters() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/builds"))
+            .and(query_param("project_id", "my api"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "builds": [] })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let query = [("project_id".to_string(), "my api".to_string())];
+        assert!(mock_client(&server).get_builds(&query).await.unwrap().builds.is_empty());
+    }
+
+    #[tokio::test]
+    async fn lists_and_deletes_one_off_pods() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/projects/api/processes"))
+            .and(query_param("one_off", "true"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "pods": [{"name": "api-run-1", "namespace": "api", "status": "Running"}]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/api/v1/projects/api/processes/api-run-1"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = mock_client(&server);
+        let pods = client.get_one_off_pods("api").await.unwrap().pods;
+        assert_eq!(pods.len(), 1);
+        client.delete_pod("api", &pods[0].name).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn manages_environment_variables() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/projects/api/environment_variables"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "environment_variables": [
+                    {"name": "RAILS_ENV", "value": "production", "secret": false},
+                    {"name": "SECRET_KEY_BASE", "value": "abc", "secret": true},
+                ]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/api/v1/projects/api/environment_variables"))
+            .and(body_json(serde_json::json!({
+                "environment_variables": [
+                    {"name": "RAILS_ENV", "value": "staging"},
+                    {"name": "API_KEY", "value": "xyz", "secret": true},
+                ]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "environment_variables": []
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/api/v1/projects/api/environment_variables/RAILS_ENV"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = mock_client(&server);
+        let variables = client.get_environment_variables("api").await.unwrap().environment_variables;
+        assert_eq!(variables.len(), 2);
+        assert!(variables[1].secret);
+
+        client
+            .set_environment_variables(
+                "api",
+                vec![
+                    EnvironmentVariableUpdate {
+                        name: "RAILS_ENV".to_string(),
+                        value: "staging".to_string(),
+                        secret: None,
+                    },
+                    EnvironmentVariableUpdate {
+                        name: "API_KEY".to_string(),
+                        value: "xyz".to_string(),
+                        secret: Some(true),
+                    },
+                ],
+            )
+            .await
+            .unwrap();
+        client.delete_environment_variable("api", "RAILS_ENV").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn encodes_variable_names_in_paths() {
+        let server = MockServer::start().await;
+        Mock::given(method("DELETE"))
+            .and(path("/api/v1/projects/api/environment_variables/A%2FB%3Fc%23d"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        mock_client(&server).delete_environment_variable("api", "A/B?c#d").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn scales_and_restarts_projects() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path("/api/v1/projects/api/scale"))
+            .and(body_json(serde_json::json!({ "processes": { "web": 3, "worker": 2 } })))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/api/v1/projects/api/restart"))
+            .and(body_json(serde_json::json!({ "process": "web" })))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = mock_client(&server);
+        let processes = BTreeMap::from([("web".to_string(), 3), ("worker".to_string(), 2)]);
+        client.scale_project("api", processes).await.unwrap();
+        client.restart_project("api", Some("web".to_string())).await.unwrap();
+    }
+}
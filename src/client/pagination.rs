@@ -0,0 +1,375 @@
+use std::collections::HashSet;
+use std::marker::PhantomData;
+
+use futures_util::stream::{self, Stream, TryStreamExt};
+use reqwest::header::{HeaderMap, LINK};
+use serde::de::DeserializeOwned;
+use url::Url;
+
+use super::{with_query, CanineClient, CanineError};
+use super::types::{
+    AddOn, AddOnsResponse, Build, BuildsResponse, Cluster, ClustersResponse, PageMeta, Project, ProjectsResponse,
+};
+
+/// A list endpoint's response body.
+pub trait Paginated: DeserializeOwned {
+    /// What the list holds.
+    type Item;
+
+    /// Splits the body into its items and pagination details.
+    fn into_parts(self) -> (Vec<Self::Item>, Option<PageMeta>);
+}
+
+impl Paginated for ProjectsResponse {
+    type Item = Project;
+
+    fn into_parts(self) -> (Vec<Project>, Option<PageMeta>) {
+        (self.projects, self.meta)
+    }
+}
+
+impl Paginated for ClustersResponse {
+    type Item = Cluster;
+
+    fn into_parts(self) -> (Vec<Cluster>, Option<PageMeta>) {
+        (self.clusters, self.meta)
+    }
+}
+
+impl Paginated for BuildsResponse {
+    type Item = Build;
+
+    fn into_parts(self) -> (Vec<Build>, Option<PageMeta>) {
+        (self.builds, self.meta)
+    }
+}
+
+impl Paginated for AddOnsResponse {
+    type Item = AddOn;
+
+    fn into_parts(self) -> (Vec<AddOn>, Option<PageMeta>) {
+        (self.add_ons, self.meta)
+    }
+}
+
+/// One page of a list.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct Page<T> {
+    /// The items on this page.
+    pub items: Vec<T>,
+    /// Pagination details, when the endpoint sends them.
+    pub meta: Option<PageMeta>,
+    next: Option<Next>,
+}
+
+impl<T> Page<T> {
+    /// Whether the server has more items after this page.
+    pub fn has_next(&self) -> bool {
+        self.next.is_some()
+    }
+}
+
+/// Where the following page is, in order of preference.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Next {
+    /// From a `Link: <...>; rel="next"` header.
+    Url(String),
+    Cursor(String),
+    Page(u32),
+}
+
+/// A request for a list endpoint, built up with query parameters and sent
+/// for one page or streamed across all of them.
+///
+/// ```no_run
+/// # use canine::client::{Build, CanineClient};
+/// # use futures_util::TryStreamExt;
+/// # async fn run(client: CanineClient) -> Result<(), canine::client::CanineError> {
+/// let builds: Vec<Build> = client.builds().project("api").per_page(100).stream().try_collect().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ListRequest<'a, R> {
+    client: &'a CanineClient,
+    path: String,
+    query: Vec<(String, String)>,
+    response: PhantomData<R>,
+}
+
+impl CanineClient {
+    /// Lists projects.
+    pub fn projects(&self) -> ListRequest<'_, ProjectsResponse> {
+        ListRequest::new(self, "/api/v1/projects")
+    }
+
+    /// Lists clusters.
+    pub fn clusters(&self) -> ListRequest<'_, ClustersResponse> {
+        ListRequest::new(self, "/api/v1/clusters")
+    }
+
+    /// Lists builds, newest first.
+    pub fn builds(&self) -> ListRequest<'_, BuildsResponse> {
+        ListRequest::new(self, "/api/v1/builds")
+    }
+
+    /// Lists add-ons.
+    pub fn add_ons(&self) -> ListRequest<'_, AddOnsResponse> {
+        ListRequest::new(self, "/api/v1/add_ons")
+    }
+}
+
+impl ListRequest<'_, BuildsResponse> {
+    /// Only builds of `project`.
+    pub fn project(self, project: impl Into<String>) -> Self {
+        self.query("project_id", project)
+    }
+}
+
+impl<'a, R: Paginated + 'a> ListRequest<'a, R> {
+    fn new(client: &'a CanineClient, path: &str) -> Self {
+        Self {
+            client,
+            path: path.to_string(),
+            query: Vec::new(),
+            response: PhantomData,
+        }
+    }
+
+    /// Sets a query parameter, replacing an earlier value.
+    pub fn query(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        let name = name.into();
+        self.query.retain(|(n, _)| *n != name);
+        self.query.push((name, value.into()));
+        self
+    }
+
+    /// Asks for page `page`, counting from 1.
+    pub fn page(self, page: u32) -> Self {
+        self.query("page", page.to_string())
+    }
+
+    /// Asks for `per_page` items per page instead of the server's default.
+    pub fn per_page(self, per_page: u32) -> Self {
+        self.query("per_page", per_page.to_string())
+    }
+
+    /// Fetches a single page.
+    pub async fn send(&self) -> Result<Page<R::Item>, CanineError> {
+        let (response, headers) = self
+            .client
+            .send_request_with_headers::<R, ()>(&with_query(&self.path, &self.query), reqwest::Method::GET, None)
+            .await?;
+        let (items, meta) = response.into_parts();
+        let next = match next_page(&headers, meta.as_ref()) {
+            Some(Next::Url(url)) => Some(Next::Url(same_origin(&self.client.base_url, url)?)),
+            next => next,
+        };
+        Ok(Page { items, meta, next })
+    }
+
+    /// Every item across all pages, fetching each page when the previous one
+    /// has been consumed. Fails with [`CanineError::PaginationLoop`] when the
+    /// server points at a page it already sent.
+    pub fn stream(self) -> impl Stream<Item = Result<R::Item, CanineError>> + 'a {
+        stream::try_unfold((Some(self), HashSet::new()), |(request, mut seen)| async move {
+            let Some(request) = request else {
+                return Ok::<_, CanineError>(None);
+            };
+            let page = request.send().await?;
+            // An empty page ends the list even if the server links another one.
+            let next = page.next.clone().filter(|_| !page.items.is_empty());
+            if let Some(next) = &next
+                && !seen.insert(next.clone())
+            {
+                return Err(CanineError::PaginationLoop);
+            }
+            let following = next.map(|next| request.at(next));
+            Ok(Some((stream::iter(page.items.into_iter().map(Ok)), (following, seen))))
+        })
+        .try_flatten()
+    }
+
+    fn at(self, next: Next) -> Self {
+        match next {
+            Next::Url(url) => Self {
+                path: url,
+                query: Vec::new(),
+                ..self
+            },
+            Next::Cursor(cursor) => self.query("cursor", cursor),
+            Next::Page(page) => self.page(page),
+        }
+    }
+}
+
+fn next_page(headers: &HeaderMap, meta: Option<&PageMeta>) -> Option<Next> {
+    if let Some(url) = headers
+        .get_all(LINK)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .find_map(next_link)
+    {
+        return Some(Next::Url(url));
+    }
+    let meta = meta?;
+    if let Some(cursor) = &meta.next_cursor {
+        return Some(Next::Cursor(cursor.clone()));
+    }
+    if let Some(page) = meta.next_page {
+        return Some(Next::Page(page));
+    }
+    match (meta.current_page, meta.total_pages) {
+        (Some(current), Some(total)) if current < total => Some(Next::Page(current + 1)),
+        _ => None,
+    }
+}
+
+/// Keeps a `Link` target only when it points at the API host, so the token
+/// is never sent to a host named by a response header.
+fn same_origin(base: &Url, link: String) -> Result<String, CanineError> {
+    let url = base.join(&link).map_err(|e| CanineError::UrlJoin(e.to_string()))?;
+    if url.origin() != base.origin() {
+        return Err(CanineError::ForeignNextLink(url.to_string()));
+    }
+    Ok(link)
+}
+
+/// The `rel="next"` target of a `Link` header (RFC 8288).
+fn next_link(header: &str) -> Option<String> {
+    header.split(',').find_map(|link| {
+        let mut parts = link.split(';');
+        let url = parts.next()?.trim().strip_prefix('<')?.strip_suffix('>')?;
+        parts
+            .filter_map(|param| param.trim().strip_prefix("rel="))
+            .any(|rel| rel.trim_matches('"').split_whitespace().any(|rel| rel == "next"))
+            .then(|| url.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::mock_client;
+    use serde_json::json;
+    use wiremock::matchers::{method, path, query_param, query_param_is_missing};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn add_on(id: i32) -> serde_json::Value {
+        json!({ "id": id, "name": "redis", "status": "installed", "cluster_id": 1, "cluster_name": "production" })
+    }
+
+    #[test]
+    fn parses_link_headers() {
+        let header = r#"<https://canine.sh/api/v1/builds?page=1>; rel="prev", <https://canine.sh/api/v1/builds?page=3>; rel="next""#;
+        assert_eq!(next_link(header).as_deref(), Some("https://canine.sh/api/v1/builds?page=3"));
+        assert_eq!(next_link(r#"<https://canine.sh/api/v1/builds?page=9>; rel="last""#), None);
+    }
+
+    #[tokio::test]
+    async fn streams_pages_from_metadata() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/add_ons"))
+            .and(query_param_is_missing("page"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "add_ons": [add_on(1), add_on(2)],
+                "meta": { "current_page": 1, "total_pages": 2 },
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/add_ons"))
+            .and(query_param("page", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "add_ons": [add_on(3)],
+                "meta": { "current_page": 2, "total_pages": 2 },
+            })))
+            .mount(&server)
+            .await;
+
+        let add_ons: Vec<AddOn> = mock_client(&server).add_ons().stream().try_collect().await.unwrap();
+        let ids: Vec<_> = add_ons.iter().map(|a| a.id).collect();
+        assert_eq!(ids, [1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn follows_cursors_and_link_headers() {
+        let server = MockServer::start().await;
+        let build = |id: i32| {
+            json!({
+                "id": id, "commit_sha": "abc", "commit_message": "Fix", "project_id": 1,
+                "project_slug": "api", "status": "completed",
+            })
+        };
+        Mock::given(method("GET"))
+            .and(path("/api/v1/builds"))
+            .and(query_param("project_id", "api"))
+            .and(query_param_is_missing("cursor"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "builds": [build(3)],
+                "meta": { "next_cursor": "c2" },
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/builds"))
+            .and(query_param("cursor", "c2"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("Link", format!(r#"<{}/api/v1/builds?after=1>; rel="next""#, server.uri()))
+                    .set_body_json(json!({ "builds": [build(2)] })),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/builds"))
+            .and(query_param("after", "1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "builds": [build(1)] })))
+            .mount(&server)
+            .await;
+
+        let client = mock_client(&server);
+        let first = client.builds().project("api").send().await.unwrap();
+        assert!(first.has_next());
+
+        let builds: Vec<Build> = client.builds().project("api").stream().try_collect().await.unwrap();
+        let ids: Vec<_> = builds.iter().map(|b| b.id).collect();
+        assert_eq!(ids, [3, 2, 1]);
+    }
+
+    #[tokio::test]
+    async fn refuses_next_links_to_other_hosts() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/add_ons"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("Link", r#"<https://evil.example.com/steal?page=2>; rel="next""#)
+                    .set_body_json(json!({ "add_ons": [add_on(1)] })),
+            )
+            .mount(&server)
+            .await;
+
+        let err = mock_client(&server).add_ons().send().await.unwrap_err();
+        assert!(matches!(err, CanineError::ForeignNextLink(url) if url.starts_with("https://evil.example.com/")));
+    }
+
+    #[tokio::test]
+    async fn stops_when_the_server_repeats_a_page() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/add_ons"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "add_ons": [add_on(1)],
+                "meta": { "next_cursor": "same" },
+            })))
+            .mount(&server)
+            .await;
+
+        let result: Result<Vec<AddOn>, _> = mock_client(&server).add_ons().stream().try_collect().await;
+        assert!(matches!(result, Err(CanineError::PaginationLoop)));
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+}
@@ -1,162 +1,624 @@
+use std::collections::BTreeMap;
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use strum::Display;
+use strum::{Display, VariantNames};
 use tabled::Tabled;
 
 use crate::kubeconfig::Kubeconfig;
 
+/// An account, the owner of clusters and projects.
 #[derive(Debug, Serialize, Deserialize, Tabled)]
+#[non_exhaustive]
 pub struct Account {
+    /// Numeric ID.
     pub id: i32,
+    /// Name used in URLs and with `--account`.
     pub slug: String,
 }
 
+/// The user a token belongs to.
 #[derive(Debug, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct User {
+    /// Numeric ID.
     pub id: i32,
+    /// Email address.
     pub email: String,
+    /// Display name.
     pub name: String,
+    /// Account requests are made for unless another is selected.
     pub current_account: Account,
+    /// Every account the user belongs to.
     pub accounts: Vec<Account>,
 }
 
+/// Lifecycle of a project.
 #[derive(Debug, Serialize, Deserialize, Display)]
 #[serde(rename_all = "lowercase")]
+#[non_exhaustive]
 pub enum ProjectStatus {
+    /// Being set up.
     Creating,
+    /// Set up and serving.
     Deployed,
+    /// Being removed.
     Destroying,
 }
 
-#[derive(Debug, Serialize, Deserialize, Display)]
+/// Lifecycle of a cluster.
+#[derive(Debug, Serialize, Deserialize, Display, VariantNames)]
 #[serde(rename_all = "lowercase")]
+#[non_exhaustive]
 pub enum ClusterStatus {
+    /// Being provisioned.
     Initializing,
+    /// Platform components are being installed.
     Installing,
+    /// Ready for projects.
     Running,
+    /// Provisioning or installation failed.
     Failed,
+    /// Being removed.
     Destroying,
+    /// Removed.
     Deleted,
 }
 
+/// Kubernetes distribution of a cluster.
 #[derive(Debug, Serialize, Deserialize, Display)]
 #[serde(rename_all = "lowercase")]
+#[non_exhaustive]
 pub enum ClusterType {
+    /// A managed or self-hosted Kubernetes cluster.
     K8s,
+    /// A k3s server.
     K3s,
 }
 
-#[derive(Debug, Serialize, Deserialize, Display, PartialEq)]
+/// Phase of a pod, as reported by Kubernetes.
+#[derive(Debug, Serialize, Deserialize, Display, VariantNames, PartialEq)]
+#[non_exhaustive]
 pub enum ProcessStatus {
+    /// Scheduled but not all containers have started.
     Pending,
+    /// All containers have started.
     Running,
+    /// All containers exited successfully.
     Succeeded,
+    /// A container exited with an error.
     Failed,
+    /// The node stopped reporting.
     Unknown,
 }
 
+/// A project: an application built from a repository and run on a cluster.
 #[derive(Debug, Serialize, Deserialize, Tabled)]
+#[non_exhaustive]
 pub struct Project {
+    /// Numeric ID.
     pub id: i32,
+    /// Name, unique within the account.
     pub name: String,
+    /// Kubernetes namespace the project runs in.
     pub namespace: String,
+    /// Repository the project is built from.
     pub repository_url: String,
+    /// Branch that is built and deployed.
     pub branch: String,
+    /// Lifecycle of the project.
     pub status: ProjectStatus,
+    /// Cluster the project runs on.
     pub cluster_id: i32,
+    /// Name of that cluster.
     pub cluster_name: String,
+    /// Dockerfile used for builds, relative to the repository root.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[tabled(display = "display_option")]
+    pub dockerfile_path: Option<String>,
+    /// Directory builds run in, relative to the repository root.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[tabled(display = "display_option")]
+    pub build_context: Option<String>,
+    /// Whether pushes to the branch are deployed automatically.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[tabled(display = "display_option")]
+    pub autodeploy: Option<bool>,
+}
+
+/// Settings of a project as sent when creating or updating it, and as kept in
+/// `canine.yaml` for `canine apply`. Fields left out are not changed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[non_exhaustive]
+pub struct ProjectSpec {
+    /// See [`Project::name`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// See [`Project::repository_url`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repository_url: Option<String>,
+    /// See [`Project::branch`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    /// See [`Project::cluster_name`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cluster_name: Option<String>,
+    /// See [`Project::dockerfile_path`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dockerfile_path: Option<String>,
+    /// See [`Project::build_context`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build_context: Option<String>,
+    /// See [`Project::autodeploy`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub autodeploy: Option<bool>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct ProjectRequest {
+    pub project: ProjectSpec,
+}
+
+/// A Kubernetes cluster connected to the account.
 #[derive(Debug, Serialize, Deserialize, Tabled)]
+#[non_exhaustive]
 pub struct Cluster {
+    /// Numeric ID.
     pub id: i32,
+    /// Name, unique within the account.
     pub name: String,
+    /// Kubernetes distribution.
     pub cluster_type: ClusterType,
+    /// Lifecycle of the cluster.
     pub status: ClusterStatus,
+    /// When the cluster was added.
     pub created_at: DateTime<Utc>,
+    /// When the cluster last changed.
     pub updated_at: DateTime<Utc>,
 }
 
+/// Pagination details sent next to the items of a list. Servers use either
+/// page numbers or an opaque cursor.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct PageMeta {
+    /// Number of this page, starting at 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_page: Option<u32>,
+    /// Number of the following page, absent on the last one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_page: Option<u32>,
+    /// Number of pages in the list.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_pages: Option<u32>,
+    /// Number of items in the list.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_count: Option<u64>,
+    /// Cursor of the following page, absent on the last one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// A page of projects.
 #[derive(Debug, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct ProjectsResponse {
+    /// The projects on this page.
     pub projects: Vec<Project>,
+    /// Present when the endpoint paginates.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<PageMeta>,
 }
 
+/// A page of clusters.
 #[derive(Debug, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct ClustersResponse {
+    /// The clusters on this page.
     pub clusters: Vec<Cluster>,
+    /// Present when the endpoint paginates.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<PageMeta>,
 }
 
+/// A page of builds.
 #[derive(Debug, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct BuildsResponse {
+    /// The builds on this page.
     pub builds: Vec<Build>,
+    /// Present when the endpoint paginates.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<PageMeta>,
 }
 
+/// A pod running one of a project's processes.
 #[derive(Debug, Serialize, Deserialize, Tabled)]
+#[non_exhaustive]
 pub struct Process {
+    /// Pod name.
     pub name: String,
+    /// Namespace of the pod.
     pub namespace: String,
+    /// Phase of the pod.
     pub status: ProcessStatus,
 }
 
+impl Process {
+    /// Name of the deployment the pod belongs to. Kubernetes names pods
+    /// `<deployment>-<replicaset hash>-<suffix>`.
+    pub fn deployment(&self) -> &str {
+        self.name.rsplitn(3, '-').nth(2).unwrap_or(&self.name)
+    }
+
+    /// Whether the pod runs `process`, whose deployment is named either
+    /// `<process>` or `<project>-<process>`.
+    pub fn runs(&self, process: &str) -> bool {
+        let deployment = self.deployment();
+        deployment == process
+            || deployment
+                .strip_suffix(process)
+                .is_some_and(|project| project.ends_with('-'))
+    }
+}
+
+/// The pods of a project.
 #[derive(Debug, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct ProcessesResponse {
+    /// The pods.
     pub pods: Vec<Process>,
 }
 
+/// Answer to a deploy request.
 #[derive(Debug, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct DeployProjectResponse {
+    /// Human readable summary.
     pub message: String,
+    /// Build started for the deploy, or the build being redeployed.
     pub build_id: i32,
 }
 
+/// A one-off pod started with [`CanineClient::create_one_off_pod`](super::CanineClient::create_one_off_pod).
 #[derive(Debug, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct Pod {
+    /// Pod name.
     pub name: String,
+    /// Namespace of the pod.
     pub namespace: String,
+    /// Phase of the pod.
     pub status: ProcessStatus,
+    /// Why the pod is in its current state, e.g. `ImagePullBackOff`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Details about the reason.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl Pod {
+    /// The status along with the reason and message, when the API sends them.
+    pub fn describe(&self) -> String {
+        let mut description = self.status.to_string();
+        if let Some(reason) = &self.reason {
+            description.push_str(&format!(": {}", reason));
+        }
+        if let Some(message) = &self.message {
+            description.push_str(&format!(" ({})", message));
+        }
+        description
+    }
+}
+
+/// An environment variable of a project.
+#[derive(Debug, Clone, Serialize, Deserialize, Tabled)]
+#[non_exhaustive]
+pub struct EnvironmentVariable {
+    /// Variable name.
+    pub name: String,
+    /// Its value. The API masks it for secrets unless asked not to.
+    pub value: String,
+    /// Whether the value is hidden from listings.
+    #[serde(default)]
+    pub secret: bool,
 }
 
+/// The environment variables of a project.
 #[derive(Debug, Serialize, Deserialize)]
-pub struct DeployProjectRequest {
+#[non_exhaustive]
+pub struct EnvironmentVariablesResponse {
+    /// The variables.
+    pub environment_variables: Vec<EnvironmentVariable>,
+}
+
+/// A variable to create or change.
+#[derive(Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct EnvironmentVariableUpdate {
+    /// Variable name.
+    pub name: String,
+    /// New value.
+    pub value: String,
+    /// Left out to keep whatever the variable was before.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<bool>,
+}
+
+impl EnvironmentVariableUpdate {
+    /// Sets `name` to `value`, marking it secret or not when `secret` is given.
+    pub fn new(name: impl Into<String>, value: impl Into<String>, secret: Option<bool>) -> Self {
+        EnvironmentVariableUpdate { name: name.into(), value: value.into(), secret }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct UpdateEnvironmentVariablesRequest {
+    pub environment_variables: Vec<EnvironmentVariableUpdate>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct ScaleProjectRequest {
+    pub processes: BTreeMap<String, u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct RestartProjectRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub process: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct DeployProjectRequest {
     pub skip_build: bool,
+    /// Deploys this build instead of the latest commit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub build_id: Option<i32>,
 }
 
+/// Credentials for a cluster.
 #[derive(Debug, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct ClusterKubeconfigResponse {
+    /// A kubeconfig with a single cluster, user and context.
     pub kubeconfig: Kubeconfig,
 }
 
+/// Progress of a build.
+#[derive(Debug, Default, Serialize, Deserialize, Display, VariantNames, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum BuildStatus {
+    /// Queued or building.
+    InProgress,
+    /// The image was built.
+    Completed,
+    /// The build failed.
+    Failed,
+    /// The build was stopped.
+    Killed,
+    /// A status added to the API after this version, or none at all.
+    #[default]
+    #[serde(other)]
+    Unknown,
+}
+
+impl BuildStatus {
+    /// Whether the build is over. An unknown status is waited on, so callers
+    /// time out rather than treat it as a result.
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, BuildStatus::Completed | BuildStatus::Failed | BuildStatus::Killed)
+    }
+}
+
+/// Progress of a rollout.
+#[derive(Debug, Serialize, Deserialize, Display, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum DeploymentStatus {
+    /// New pods are starting.
+    InProgress,
+    /// Every process runs the new image.
+    Completed,
+    /// The rollout did not finish.
+    Failed,
+}
+
+/// A rollout of a build.
 #[derive(Debug, Serialize, Deserialize, Tabled)]
+#[non_exhaustive]
+pub struct Deployment {
+    /// Numeric ID.
+    pub id: i32,
+    /// Build that was rolled out.
+    pub build_id: i32,
+    /// Progress of the rollout.
+    pub status: DeploymentStatus,
+    /// Only included in a project's deployment history.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[tabled(display = "display_option")]
+    pub commit_sha: Option<String>,
+    /// Only included in a project's deployment history.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[tabled(display = "display_option")]
+    pub commit_message: Option<String>,
+    /// When the rollout started.
+    pub created_at: DateTime<Utc>,
+}
+
+/// A project's deployment history.
+#[derive(Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DeploymentsResponse {
+    /// The deployments, newest first.
+    pub deployments: Vec<Deployment>,
+}
+
+fn display_option<T: std::fmt::Display>(value: &Option<T>) -> String {
+    value.as_ref().map(ToString::to_string).unwrap_or_default()
+}
+
+/// An image build of a commit.
+#[derive(Debug, Serialize, Deserialize, Tabled)]
+#[non_exhaustive]
 pub struct Build {
+    /// Numeric ID.
     pub id: i32,
+    /// Commit that was built.
     pub commit_sha: String,
+    /// Its message.
     pub commit_message: String,
+    /// Project the build belongs to.
     pub project_id: i32,
-    pub project_slug: String
+    /// Name of that project.
+    pub project_slug: String,
+    /// Progress of the build.
+    #[serde(default)]
+    pub status: BuildStatus,
+    /// The user who pushed or clicked deploy, absent for automatic builds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[tabled(display = "display_option")]
+    pub triggered_by: Option<String>,
+    /// Absent while the build is queued.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[tabled(display = "display_time")]
+    pub started_at: Option<DateTime<Utc>>,
+    /// Absent until the build is over.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[tabled(rename = "duration", display("display_duration", self))]
+    pub finished_at: Option<DateTime<Utc>>,
+    /// The rollout of this build, if there was one.
+    #[serde(default)]
+    #[tabled(display = "display_deployment")]
+    pub deployment: Option<Deployment>,
+}
+
+impl Build {
+    /// Time spent building, up to now for builds that are still running.
+    pub fn duration(&self) -> Option<chrono::Duration> {
+        let started_at = self.started_at?;
+        Some(self.finished_at.unwrap_or_else(Utc::now) - started_at)
+    }
 }
 
+fn display_time(value: &Option<DateTime<Utc>>) -> String {
+    value.map(|time| time.to_string()).unwrap_or_default()
+}
+
+fn display_deployment(value: &Option<Deployment>) -> String {
+    value
+        .as_ref()
+        .map(|deployment| format!("{} ({})", deployment.id, deployment.status))
+        .unwrap_or_default()
+}
+
+fn display_duration(_: &Option<DateTime<Utc>>, build: &Build) -> String {
+    build.duration().map(format_duration).unwrap_or_default()
+}
+
+/// Formats a duration compactly, e.g. `4m 05s`.
+pub fn format_duration(duration: chrono::Duration) -> String {
+    let seconds = duration.num_seconds().max(0);
+    match (seconds / 3600, seconds / 60 % 60, seconds % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m {:02}s", m, s),
+        (h, m, _) => format!("{}h {:02}m", h, m),
+    }
+}
+
+/// Output of a build so far.
+#[derive(Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct BuildLogs {
+    /// Everything the build printed.
+    pub output: String,
+}
+
+/// A page of add-ons.
 #[derive(Debug, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct AddOnsResponse {
+    /// The add-ons on this page.
     pub add_ons: Vec<AddOn>,
+    /// Present when the endpoint paginates.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<PageMeta>,
 }
 
+/// A Helm chart installed on a cluster, such as a database.
 #[derive(Debug, Serialize, Deserialize, Tabled)]
+#[non_exhaustive]
 pub struct AddOn {
+    /// Numeric ID.
     pub id: i32,
+    /// Name, unique within the cluster.
     pub name: String,
+    /// Lifecycle of the add-on.
     pub status: AddOnStatus,
+    /// Cluster the add-on is installed on.
     pub cluster_id: i32,
+    /// Name of that cluster.
     pub cluster_name: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Display)]
+/// Lifecycle of an add-on.
+#[derive(Debug, Serialize, Deserialize, Display, VariantNames)]
 #[serde(rename_all = "lowercase")]
+#[non_exhaustive]
 pub enum AddOnStatus {
+    /// Being installed.
     Installing,
+    /// Installed and running.
     Installed,
+    /// Being removed.
     Uninstalling,
+    /// Removed.
     Uninstalled,
+    /// Installing or updating failed.
     Failed,
+    /// A new version or new values are being applied.
     Updating,
-}
\ No newline at end of file
+}
+
+/// A pending browser login. The user approves it by entering `user_code` at
+/// `verification_uri`.
+#[derive(Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DeviceAuthorization {
+    /// Secret passed to [`CanineClient::request_device_token`](super::CanineClient::request_device_token).
+    pub device_code: String,
+    /// Code the user types in the browser.
+    pub user_code: String,
+    /// Page where the code is entered.
+    pub verification_uri: String,
+    /// The same page with the code filled in.
+    #[serde(default)]
+    pub verification_uri_complete: Option<String>,
+    /// Seconds until the login expires.
+    pub expires_in: u64,
+    /// Seconds to wait between polls.
+    pub interval: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct DeviceTokenRequest {
+    pub device_code: String,
+}
+
+/// The token issued once a device login is approved.
+#[derive(Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DeviceToken {
+    /// API token.
+    pub token: String,
+}
+
+/// Error body returned while a device authorization is not yet approved.
+#[derive(Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DeviceTokenError {
+    /// OAuth error code, such as `authorization_pending` or `slow_down`.
+    pub error: String,
+}
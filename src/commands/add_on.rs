@@ -1,12 +1,17 @@
-use tabled::Table;
-
 use crate::client::{CanineClient};
-use crate::cli::{AddOnId};
+use crate::cli::{AddOnId, AddOnList};
+use crate::output::{self, OutputFormat};
 
-pub async fn handle_list(client: &CanineClient) -> Result<(), Box<dyn std::error::Error>> {
-    let result = client.get_add_ons().await?;
-    println!("{}", Table::new(result.add_ons));
-    Ok(())
+pub async fn handle_list(
+    client: &CanineClient,
+    params: &AddOnList,
+    format: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if params.watch.watch {
+        return output::watch(&params.watch, &params.list, format, || output::fetch(&params.pages, &params.list, client.add_ons())).await;
+    }
+    let add_ons = output::fetch(&params.pages, &params.list, client.add_ons()).await?;
+    output::print_selected(add_ons, format, &params.list)
 }
 
 pub async fn handle_restart(client: &CanineClient, add_on_id: &AddOnId) -> Result<(), Box<dyn std::error::Error>> {
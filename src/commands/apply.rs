@@ -0,0 +1,197 @@
+use colored::Colorize;
+use serde_json::Value;
+
+use crate::cli::ApplyCmd;
+use crate::client::{ApiError, CanineClient, CanineError, Project, ProjectSpec};
+
+/// What `apply` has to do to bring a project in line with its definition.
+#[derive(Debug, PartialEq)]
+enum Plan {
+    Create(ProjectSpec),
+    /// Only the settings that differ from the current project.
+    Update(ProjectSpec),
+    UpToDate,
+}
+
+pub async fn handle_apply(client: &CanineClient, params: &ApplyCmd) -> Result<(), Box<dyn std::error::Error>> {
+    let contents = std::fs::read_to_string(&params.file)
+        .map_err(|e| format!("Could not read {}: {}", params.file.display(), e))?;
+    let spec: ProjectSpec = serde_yaml::from_str(&contents)
+        .map_err(|e| format!("Invalid project definition in {}: {}", params.file.display(), e))?;
+    let name = spec
+        .name
+        .clone()
+        .ok_or_else(|| format!("{} does not set a project name", params.file.display()))?;
+
+    let current = match client.get_project(&name).await {
+        Ok(project) => Some(project),
+        Err(CanineError::Api(ApiError::NotFound { .. })) => None,
+        Err(e) => return Err(e.into()),
+    };
+    let plan = plan(spec, current.as_ref())?;
+
+    if plan == Plan::UpToDate {
+        eprintln!("{} Project {} is up to date", "✓".green(), name.cyan());
+        return Ok(());
+    }
+    for line in diff(&plan, current.as_ref()) {
+        match line.chars().next() {
+            Some('+') => println!("{}", line.green()),
+            Some('~') => println!("{}", line.yellow()),
+            _ => println!("{}", line),
+        }
+    }
+    if params.dry_run {
+        eprintln!("{} Dry run, nothing was changed", "→".cyan());
+        return Ok(());
+    }
+
+    match plan {
+        Plan::Create(spec) => {
+            client.create_project(spec).await?;
+            eprintln!("{} Created project {}", "✓".green(), name.cyan());
+        }
+        Plan::Update(spec) => {
+            client.update_project(&name, spec).await?;
+            eprintln!("{} Updated project {}", "✓".green(), name.cyan());
+        }
+        Plan::UpToDate => {}
+    }
+    Ok(())
+}
+
+fn plan(spec: ProjectSpec, current: Option<&Project>) -> Result<Plan, String> {
+    let Some(project) = current else {
+        if spec.repository_url.is_none() || spec.cluster_name.is_none() {
+            return Err("New projects need repository_url and cluster_name".to_string());
+        }
+        return Ok(Plan::Create(spec));
+    };
+
+    fn changed<T: PartialEq + Clone>(desired: &Option<T>, current: Option<&T>) -> Option<T> {
+        desired.as_ref().filter(|d| Some(*d) != current).cloned()
+    }
+    let mut changes = ProjectSpec::default();
+    changes.repository_url = changed(&spec.repository_url, Some(&project.repository_url));
+    changes.branch = changed(&spec.branch, Some(&project.branch));
+    changes.cluster_name = changed(&spec.cluster_name, Some(&project.cluster_name));
+    changes.dockerfile_path = changed(&spec.dockerfile_path, project.dockerfile_path.as_ref());
+    changes.build_context = changed(&spec.build_context, project.build_context.as_ref());
+    changes.autodeploy = changed(&spec.autodeploy, project.autodeploy.as_ref());
+    if changes == ProjectSpec::default() {
+        Ok(Plan::UpToDate)
+    } else {
+        Ok(Plan::Update(changes))
+    }
+}
+
+/// One line per setting, `+` for new projects and `~ old -> new` for changes.
+fn diff(plan: &Plan, current: Option<&Project>) -> Vec<String> {
+    // Spec and project share field names, so both are compared as JSON objects.
+    let fields = |value: Value| match value {
+        Value::Object(map) => map,
+        _ => Default::default(),
+    };
+    let before = fields(serde_json::to_value(current).unwrap_or_default());
+    let show = |value: &Value| match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+
+    match plan {
+        Plan::Create(spec) => {
+            let mut lines = vec![format!("+ project {}", spec.name.as_deref().unwrap_or_default())];
+            for (key, value) in fields(serde_json::to_value(spec).unwrap_or_default()) {
+                if key != "name" {
+                    lines.push(format!("+   {}: {}", key, show(&value)));
+                }
+            }
+            lines
+        }
+        Plan::Update(changes) => {
+            let name = current.map(|p| p.name.as_str()).unwrap_or_default();
+            let mut lines = vec![format!("~ project {}", name)];
+            for (key, value) in fields(serde_json::to_value(changes).unwrap_or_default()) {
+                let old = before.get(&key).map(show).unwrap_or_else(|| "(unset)".to_string());
+                lines.push(format!("~   {}: {} -> {}", key, old, show(&value)));
+            }
+            lines
+        }
+        Plan::UpToDate => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn project() -> Project {
+        serde_json::from_value(json!({
+            "id": 1,
+            "name": "api",
+            "namespace": "api",
+            "repository_url": "https://github.com/acme/api",
+            "branch": "main",
+            "status": "deployed",
+            "cluster_id": 1,
+            "cluster_name": "production",
+            "dockerfile_path": "Dockerfile",
+            "autodeploy": true,
+        }))
+        .unwrap()
+    }
+
+    fn spec(yaml: &str) -> ProjectSpec {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn plans_only_changed_settings() {
+        let desired = spec(
+            "name: api\nrepository_url: https://github.com/acme/api\nbranch: develop\n\
+             build_context: ./app\nautodeploy: true\n",
+        );
+        let plan = plan(desired, Some(&project())).unwrap();
+
+        assert_eq!(
+            plan,
+            Plan::Update(spec("branch: develop\nbuild_context: ./app\n"))
+        );
+        assert_eq!(
+            diff(&plan, Some(&project())),
+            [
+                "~ project api",
+                "~   branch: main -> develop",
+                "~   build_context: (unset) -> ./app",
+            ]
+        );
+    }
+
+    #[test]
+    fn matching_definition_is_up_to_date() {
+        let desired = spec("name: api\nbranch: main\ncluster_name: production\n");
+        assert_eq!(plan(desired, Some(&project())).unwrap(), Plan::UpToDate);
+    }
+
+    #[test]
+    fn creates_missing_projects() {
+        let desired = spec("name: web\nrepository_url: https://github.com/acme/web\ncluster_name: production\n");
+        let plan = plan(desired, None).unwrap();
+        assert_eq!(
+            diff(&plan, None),
+            [
+                "+ project web",
+                "+   cluster_name: production",
+                "+   repository_url: https://github.com/acme/web",
+            ]
+        );
+
+        assert!(super::plan(spec("name: web\n"), None).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_settings() {
+        assert!(serde_yaml::from_str::<ProjectSpec>("name: api\nbranhc: main\n").is_err());
+    }
+}
@@ -1,65 +1,238 @@
+use std::io::Read;
+use std::time::Duration;
+
 use colored::Colorize;
-use tabled::Table;
+use tokio::time::Instant;
 
 use crate::cli::AuthLogin;
-use crate::client::{Auth, CanineClient, CanineError};
-use crate::config::CanineConfig;
+use crate::client::{
+    ApiError, Auth, CanineClient, CanineError, ClientOptions, DeviceAuthorization, DeviceTokenError,
+};
+use crate::config::{CanineConfig, Profile};
+use crate::output::{self, OutputFormat};
 
-pub async fn handle_login(login: AuthLogin) -> Result<(), Box<dyn std::error::Error>> {
-    let host = match login.host {
-        Some(h) => h,
-        None => {
-            println!("No host specified, using {}", CanineConfig::DEFAULT_HOST.cyan());
-            CanineConfig::DEFAULT_HOST.to_string()
+pub async fn handle_login(
+    config: &CanineConfig,
+    credentials: Profile,
+    options: ClientOptions,
+    login: AuthLogin,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let host = login_host(config, credentials.host);
+
+    let token = if login.with_token {
+        let mut input = String::new();
+        std::io::stdin().read_to_string(&mut input)?;
+        let token = input.trim().to_string();
+        if token.is_empty() {
+            return Err("No token received on stdin".into());
         }
+        token
+    } else if let Some(token) = credentials.token {
+        token
+    } else {
+        let client = CanineClient::with_options(&host, Auth::None, None, options.clone())?;
+        device_login(&client).await?
     };
 
-    let client = CanineClient::new(&host, Auth::ApiKey(login.token.clone()), login.account)?;
+    let client =
+        CanineClient::with_options(&host, Auth::ApiKey(token.clone()), credentials.account, options)?;
 
     match client.me().await {
         Ok(me) => {
             CanineConfig {
+                profile: config.profile.clone(),
                 host: Some(host),
-                token: Some(login.token),
+                token: Some(token),
                 account: Some(me.current_account.slug),
             }
             .save()?;
-            println!("{} Authenticated as {}", "✓".green(), me.email.green());
+            println!(
+                "{} Authenticated as {} (profile: {})",
+                "✓".green(),
+                me.email.green(),
+                config.profile.cyan()
+            );
             println!(
                 "  Credentials saved to {}",
                 CanineConfig::config_path().to_str().unwrap().dimmed()
             );
         }
-        Err(CanineError::Api(api_err)) => {
-            println!("{} Authentication failed: {}", "✗".red(), api_err);
-        }
+        Err(CanineError::Api(api_err)) => return Err(api_err.into()),
         Err(e) => return Err(Box::new(e)),
     };
 
     Ok(())
 }
 
-pub async fn handle_logout() -> Result<(), Box<dyn std::error::Error>> {
-    CanineConfig::clear();
+/// The host to log in to: `--host` or `CANINE_HOST`, then the host already
+/// stored in the profile, then the default.
+fn login_host(config: &CanineConfig, host: Option<String>) -> String {
+    match host.or_else(|| config.host.clone()) {
+        Some(h) => h,
+        None => {
+            eprintln!("No host specified, using {}", CanineConfig::DEFAULT_HOST.cyan());
+            CanineConfig::DEFAULT_HOST.to_string()
+        }
+    }
+}
+
+async fn device_login(client: &CanineClient) -> Result<String, CanineError> {
+    let authorization = client.start_device_authorization().await?;
+
+    eprintln!(
+        "Open {} and enter the code {}",
+        authorization
+            .verification_uri_complete
+            .as_deref()
+            .unwrap_or(&authorization.verification_uri)
+            .cyan(),
+        authorization.user_code.bold()
+    );
+    eprintln!("{} Waiting for approval...", "→".cyan());
+
+    wait_for_device_token(client, &authorization).await
+}
+
+/// Polls the token endpoint until the user approves or rejects the login,
+/// following the device authorization grant (RFC 8628).
+async fn wait_for_device_token(
+    client: &CanineClient,
+    authorization: &DeviceAuthorization,
+) -> Result<String, CanineError> {
+    let deadline = Instant::now() + Duration::from_secs(authorization.expires_in);
+    let mut interval = Duration::from_secs(authorization.interval);
+
+    loop {
+        if Instant::now() >= deadline {
+            return Err(CanineError::DeviceAuthorizationExpired);
+        }
+        tokio::time::sleep(interval).await;
+
+        match client.request_device_token(&authorization.device_code).await {
+            Ok(token) => return Ok(token.token),
+            Err(CanineError::Api(ApiError::ClientError { status, body })) => {
+                let reason = serde_json::from_str::<DeviceTokenError>(&body)
+                    .map(|e| e.error)
+                    .unwrap_or_default();
+                match reason.as_str() {
+                    "authorization_pending" => {}
+                    "slow_down" => interval += Duration::from_secs(5),
+                    "access_denied" => return Err(CanineError::DeviceAuthorizationDenied),
+                    "expired_token" => return Err(CanineError::DeviceAuthorizationExpired),
+                    _ => return Err(ApiError::ClientError { status, body }.into()),
+                }
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+pub async fn handle_logout(config: &CanineConfig) -> Result<(), Box<dyn std::error::Error>> {
+    config.clear()?;
     println!("{} Logged out successfully", "✓".green());
     Ok(())
 }
 
-pub async fn handle_status(config: &CanineConfig) -> Result<(), Box<dyn std::error::Error>> {
-    let token = config.token.clone().ok_or_else(|| CanineError::NoToken)?;
+pub async fn handle_status(
+    client: &CanineClient,
+    format: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let response = client.me().await?;
+    output::print_item(&response, format, |response| {
+        println!("Logged in as {}  (account: {})", response.email.green(), response.current_account.slug.cyan());
+        println!();
+        println!("Available accounts:");
+        println!("{}", output::table(&response.accounts, false));
+    })
+}
 
-    let host = config
-        .host
-        .clone()
-        .unwrap_or_else(|| CanineConfig::DEFAULT_HOST.to_string());
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
 
-    let client = CanineClient::new(&host, Auth::ApiKey(token), config.account.clone())?;
+    fn authorization(expires_in: u64) -> DeviceAuthorization {
+        serde_json::from_value(json!({
+            "device_code": "device-123",
+            "user_code": "ABCD-EFGH",
+            "verification_uri": "https://canine.sh/device",
+            "expires_in": expires_in,
+            "interval": 0,
+        }))
+        .unwrap()
+    }
 
-    let response = client.me().await?;
-    println!("Logged in as {}  (account: {})", response.email.green(), response.current_account.slug.cyan());
-    println!();
-    println!("Available accounts:");
-    println!("{}", Table::new(response.accounts));
+    async fn token_response(server: &MockServer, response: ResponseTemplate, times: u64) {
+        Mock::given(method("POST"))
+            .and(path("/api/v1/device_authorizations/token"))
+            .and(body_json(json!({ "device_code": "device-123" })))
+            .respond_with(response)
+            .up_to_n_times(times)
+            .mount(server)
+            .await;
+    }
 
-    Ok(())
+    #[tokio::test]
+    async fn polls_until_approved() {
+        let server = MockServer::start().await;
+        let pending = ResponseTemplate::new(400).set_body_json(json!({ "error": "authorization_pending" }));
+        token_response(&server, pending, 2).await;
+        token_response(&server, ResponseTemplate::new(200).set_body_json(json!({ "token": "issued" })), 1).await;
+
+        let client = CanineClient::new(server.uri(), Auth::None, None).unwrap();
+        let token = wait_for_device_token(&client, &authorization(60)).await.unwrap();
+
+        assert_eq!(token, "issued");
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn stops_when_denied() {
+        let server = MockServer::start().await;
+        token_response(&server, ResponseTemplate::new(400).set_body_json(json!({ "error": "access_denied" })), 1).await;
+
+        let client = CanineClient::new(server.uri(), Auth::None, None).unwrap();
+        let result = wait_for_device_token(&client, &authorization(60)).await;
+
+        assert!(matches!(result, Err(CanineError::DeviceAuthorizationDenied)));
+    }
+
+    #[test]
+    fn logs_in_again_to_the_profile_host() {
+        let config = CanineConfig {
+            profile: "self-hosted".to_string(),
+            host: Some("https://canine.example.com".to_string()),
+            token: Some("old".to_string()),
+            account: Some("acme".to_string()),
+        };
+
+        assert_eq!(login_host(&config, None), "https://canine.example.com");
+        assert_eq!(
+            login_host(&config, Some("https://other.example.com".to_string())),
+            "https://other.example.com"
+        );
+    }
+
+    #[test]
+    fn logs_in_to_the_default_host_for_new_profiles() {
+        let config = CanineConfig {
+            profile: "new".to_string(),
+            host: None,
+            token: None,
+            account: None,
+        };
+
+        assert_eq!(login_host(&config, None), CanineConfig::DEFAULT_HOST);
+    }
+
+    #[tokio::test]
+    async fn gives_up_when_expired() {
+        let server = MockServer::start().await;
+        let client = CanineClient::new(server.uri(), Auth::None, None).unwrap();
+        let result = wait_for_device_token(&client, &authorization(0)).await;
+
+        assert!(matches!(result, Err(CanineError::DeviceAuthorizationExpired)));
+    }
 }
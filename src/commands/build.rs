@@ -1,12 +1,303 @@
-use crate::client::{CanineClient};
-use tabled::Table;
+use std::io::Write;
+use std::time::Duration;
 
-pub async fn handle_list(client: &CanineClient, project_id: &Option<String>) -> Result<(), Box<dyn std::error::Error>> {
-    let builds = client.get_builds(project_id).await?.builds;
-    println!("{}", Table::new(builds));
-    Ok(())
+use colored::Colorize;
+use tokio::time::Instant;
+
+use crate::cli::{BuildList, BuildLogsArgs};
+use crate::client::{format_duration, Build, BuildStatus, CanineClient, Deployment, DeploymentStatus};
+use crate::output::{self, OutputFormat};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+pub async fn handle_list(
+    client: &CanineClient,
+    project_id: &Option<String>,
+    params: &BuildList,
+    format: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let query = output::query::<Build>(&params.list);
+    let request = || {
+        let request = query
+            .iter()
+            .fold(client.builds(), |request, (name, value)| request.query(name, value));
+        match project_id {
+            Some(project_id) => request.project(project_id),
+            None => request,
+        }
+    };
+    if params.watch.watch {
+        return output::watch(&params.watch, &params.list, format, || output::fetch(&params.pages, &params.list, request())).await;
+    }
+    let builds = output::fetch(&params.pages, &params.list, request()).await?;
+    output::print_selected(builds, format, &params.list)
 }
 
-pub async fn handle_kill(client: &CanineClient, build_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+pub async fn handle_kill(client: &CanineClient, build_id: i32) -> Result<(), Box<dyn std::error::Error>> {
     Ok(client.kill_build(build_id).await?)
-}
\ No newline at end of file
+}
+
+pub async fn handle_logs(client: &CanineClient, params: &BuildLogsArgs) -> Result<(), Box<dyn std::error::Error>> {
+    if !params.follow {
+        print!("{}", client.get_build_logs(params.build).await?.output);
+        return Ok(());
+    }
+
+    let deadline = Instant::now() + Duration::from_secs(params.timeout);
+    let build = follow_build(client, params.build, deadline, &mut std::io::stdout()).await?;
+    let took = build.duration().map(|d| format!(" in {}", format_duration(d))).unwrap_or_default();
+    if build.status != BuildStatus::Completed {
+        // A failing exit status lets scripts stop on broken builds.
+        return Err(format!("Build {} {}{}", build.id, build.status, took).into());
+    }
+    eprintln!("{} Build {} completed{}", "✓".green(), build.id, took);
+    Ok(())
+}
+
+/// Streams the build log into `out` until the build reaches a terminal state.
+pub async fn follow_build(
+    client: &CanineClient,
+    build_id: i32,
+    deadline: Instant,
+    out: &mut dyn Write,
+) -> Result<Build, Box<dyn std::error::Error>> {
+    let mut printed = 0;
+    loop {
+        let build = client.get_build(build_id).await?;
+        let logs = client.get_build_logs(build_id).await?;
+        if logs.output.len() < printed {
+            printed = 0;
+        }
+        if let Some(new) = logs.output.get(printed..) {
+            out.write_all(new.as_bytes())?;
+            out.flush()?;
+            printed = logs.output.len();
+        }
+
+        if build.status.is_terminal() {
+            return Ok(build);
+        }
+        if Instant::now() >= deadline {
+            return Err(format!("Timed out waiting for build {}", build_id).into());
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Waits for the deployment created by a build to finish rolling out.
+pub async fn wait_for_rollout(
+    client: &CanineClient,
+    build_id: i32,
+    deadline: Instant,
+) -> Result<Deployment, Box<dyn std::error::Error>> {
+    loop {
+        let build = client.get_build(build_id).await?;
+        if let Some(deployment) = build.deployment
+            && deployment.status != DeploymentStatus::InProgress
+        {
+            return Ok(deployment);
+        }
+        if Instant::now() >= deadline {
+            return Err(format!("Timed out waiting for build {} to roll out", build_id).into());
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Follows a build through to the end of its rollout, failing when either step fails.
+pub async fn wait_for_deployment(
+    client: &CanineClient,
+    build_id: i32,
+    timeout: Duration,
+) -> Result<Deployment, Box<dyn std::error::Error>> {
+    let deadline = Instant::now() + timeout;
+
+    eprintln!("{} Streaming build {}...", "→".cyan(), build_id);
+    let build = follow_build(client, build_id, deadline, &mut std::io::stderr()).await?;
+    if build.status != BuildStatus::Completed {
+        eprintln!("{} Build {} finished with status {}", "✗".red(), build_id, build.status);
+        return Err(format!("Build {} {}", build_id, build.status).into());
+    }
+    eprintln!("{} Build {} completed", "✓".green(), build_id);
+
+    eprintln!("{} Waiting for rollout...", "→".cyan());
+    let deployment = wait_for_rollout(client, build_id, deadline).await?;
+    if deployment.status == DeploymentStatus::Failed {
+        eprintln!("{} Deployment {} failed", "✗".red(), deployment.id);
+        return Err(format!("Deployment {} failed", deployment.id).into());
+    }
+    eprintln!("{} Deployment {} completed", "✓".green(), deployment.id);
+
+    Ok(deployment)
+}
+
+/// Waits for the first deployment of `project` newer than `after` to finish
+/// rolling out, for deploys that do not go through a build.
+pub async fn wait_for_new_deployment(
+    client: &CanineClient,
+    project: &str,
+    after: i32,
+    timeout: Duration,
+) -> Result<Deployment, Box<dyn std::error::Error>> {
+    let deadline = Instant::now() + timeout;
+    eprintln!("{} Waiting for rollout...", "→".cyan());
+    loop {
+        let deployment = client
+            .get_deployments(project)
+            .await?
+            .deployments
+            .into_iter()
+            .filter(|d| d.id > after)
+            .min_by_key(|d| d.id);
+        if let Some(deployment) = deployment {
+            match deployment.status {
+                DeploymentStatus::Completed => {
+                    eprintln!("{} Deployment {} completed", "✓".green(), deployment.id);
+                    return Ok(deployment);
+                }
+                DeploymentStatus::Failed => {
+                    eprintln!("{} Deployment {} failed", "✗".red(), deployment.id);
+                    return Err(format!("Deployment {} failed", deployment.id).into());
+                }
+                _ => {}
+            }
+        }
+        if Instant::now() >= deadline {
+            return Err(format!("Timed out waiting for {} to roll out", project).into());
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::mock_client;
+    use serde_json::json;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn build_json(status: &str, deployment: Option<&str>) -> serde_json::Value {
+        json!({
+            "id": 42,
+            "commit_sha": "abc123",
+            "commit_message": "Fix things",
+            "project_id": 1,
+            "project_slug": "api",
+            "status": status,
+            "triggered_by": "jane",
+            "started_at": "2026-01-01T00:00:00Z",
+            "finished_at": "2026-01-01T00:04:05Z",
+            "deployment": deployment.map(|status| json!({
+                "id": 7,
+                "build_id": 42,
+                "status": status,
+                "created_at": "2026-01-01T00:00:00Z",
+            })),
+        })
+    }
+
+    async fn mock_build(server: &MockServer, status: &str, deployment: Option<&str>) {
+        Mock::given(method("GET"))
+            .and(path("/api/v1/builds/42"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(build_json(status, deployment)))
+            .mount(server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/builds/42/logs"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "output": "step 1\nstep 2\n" })))
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn follow_build_streams_log_until_terminal() {
+        let server = MockServer::start().await;
+        mock_build(&server, "completed", None).await;
+
+        let mut out = Vec::new();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let build = follow_build(&mock_client(&server), 42, deadline, &mut out).await.unwrap();
+
+        assert_eq!(build.status, BuildStatus::Completed);
+        assert_eq!(String::from_utf8(out).unwrap(), "step 1\nstep 2\n");
+    }
+
+    #[test]
+    fn lists_build_timing() {
+        let build: Build = serde_json::from_value(build_json("completed", None)).unwrap();
+        assert_eq!(build.duration(), Some(chrono::Duration::seconds(245)));
+
+        let table = output::table(&[build], false).to_string();
+        assert!(table.contains("triggered_by"));
+        assert!(table.contains("jane"));
+        assert!(table.contains("4m 05s"));
+    }
+
+    #[test]
+    fn tolerates_missing_and_new_statuses() {
+        let build: Build = serde_json::from_value(build_json("queued", None)).unwrap();
+        assert_eq!(build.status, BuildStatus::Unknown);
+        assert!(!build.status.is_terminal());
+
+        let mut json = build_json("completed", None);
+        json.as_object_mut().unwrap().remove("status");
+        let build: Build = serde_json::from_value(json).unwrap();
+        assert_eq!(build.status, BuildStatus::Unknown);
+    }
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(format_duration(chrono::Duration::seconds(9)), "9s");
+        assert_eq!(format_duration(chrono::Duration::seconds(3725)), "1h 02m");
+    }
+
+    #[tokio::test]
+    async fn wait_for_deployment_fails_when_build_fails() {
+        let server = MockServer::start().await;
+        mock_build(&server, "failed", None).await;
+
+        let result = wait_for_deployment(&mock_client(&server), 42, Duration::from_secs(5)).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn wait_for_deployment_fails_when_rollout_fails() {
+        let server = MockServer::start().await;
+        mock_build(&server, "completed", Some("failed")).await;
+
+        let result = wait_for_deployment(&mock_client(&server), 42, Duration::from_secs(5)).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn wait_for_deployment_returns_completed_deployment() {
+        let server = MockServer::start().await;
+        mock_build(&server, "completed", Some("completed")).await;
+
+        let deployment = wait_for_deployment(&mock_client(&server), 42, Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(deployment.status, DeploymentStatus::Completed);
+    }
+
+    #[tokio::test]
+    async fn wait_for_new_deployment_ignores_older_deployments() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/projects/api/deployments"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "deployments": [
+                    { "id": 8, "build_id": 40, "status": "completed", "created_at": "2026-01-02T00:00:00Z" },
+                    { "id": 7, "build_id": 42, "status": "failed", "created_at": "2026-01-01T00:00:00Z" },
+                ]
+            })))
+            .mount(&server)
+            .await;
+
+        let deployment = wait_for_new_deployment(&mock_client(&server), "api", 7, Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(deployment.id, 8);
+    }
+}
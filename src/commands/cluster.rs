@@ -1,11 +1,18 @@
-use tabled::Table;
 use colored::Colorize;
+use std::fs;
 use std::io;
+use std::path::Path;
 use std::process::Command;
-use crate::cli::ClusterId;
+use crate::cli::{ClusterId, ClusterList, DownloadKubeconfigParams};
 use crate::client::CanineClient;
-use crate::config::CanineConfig;
-use crate::kubeconfig::kubeconfig_to_yaml;
+use crate::config::{CanineConfig, Profile};
+use crate::kubeconfig::{
+    default_kubeconfig_path, kubeconfig_to_yaml, merge_kubeconfig, parse_kubeconfig_yaml,
+    prefix_names, Kubeconfig,
+};
+use crate::commands::kubeconfig;
+use crate::secrets;
+use crate::output::{self, OutputFormat};
 
 pub enum TelepresenceError {
     NotFound,
@@ -35,20 +42,87 @@ pub fn gate_telepresence() -> Result<(), TelepresenceError> {
 }
 
 
-pub async fn handle_list(client: &CanineClient) -> Result<(), Box<dyn std::error::Error>> {
-    let clusters = client.get_clusters().await?.clusters;
-    println!("{}", Table::new(clusters));
-    Ok(())
+pub async fn handle_list(
+    client: &CanineClient,
+    params: &ClusterList,
+    format: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if params.watch.watch {
+        return output::watch(&params.watch, &params.list, format, || output::fetch(&params.pages, &params.list, client.clusters())).await;
+    }
+    let clusters = output::fetch(&params.pages, &params.list, client.clusters()).await?;
+    output::print_selected(clusters, format, &params.list)
 }
 
 pub async fn handle_download_kubeconfig(
     config: &CanineConfig,
     client: &CanineClient,
-    id: &ClusterId,
+    credentials: &Profile,
+    params: &DownloadKubeconfigParams,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let kubeconfig = client.download_kubeconfig_file(&id.cluster).await?;
-    let yaml = kubeconfig_to_yaml(&kubeconfig.kubeconfig)?;
-    config.save_kubeconfig(yaml)?;
+    let mut kubeconfig = kubeconfig::download(client, &params.cluster).await?;
+
+    if !params.merge && !params.per_cluster {
+        let yaml = kubeconfig_to_yaml(&kubeconfig)?;
+        return match &params.path {
+            Some(path) => write_kubeconfig(path, &yaml),
+            None => config.save_kubeconfig(yaml),
+        };
+    }
+
+    let prefix = format!(
+        "canine-{}-{}",
+        credentials.account.as_deref().unwrap_or("default"),
+        params.cluster
+    );
+    prefix_names(&mut kubeconfig, &prefix);
+
+    if params.per_cluster {
+        let path = CanineConfig::cluster_credential_path(&prefix);
+        return write_kubeconfig(&path, &kubeconfig_to_yaml(&kubeconfig)?);
+    }
+
+    let path = params.path.clone().unwrap_or_else(default_kubeconfig_path);
+    let mut target = if path.exists() {
+        let contents = fs::read_to_string(&path)?;
+        if contents.trim().is_empty() {
+            Kubeconfig::default()
+        } else {
+            parse_kubeconfig_yaml(&contents)
+                .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?
+        }
+    } else {
+        Kubeconfig::default()
+    };
+
+    let report = merge_kubeconfig(&mut target, kubeconfig, params.set_context);
+    write_kubeconfig(&path, &kubeconfig_to_yaml(&target)?)?;
+    kubeconfig::warn_file(&target, &path);
+    for entry in &report.added {
+        eprintln!("  {} {}", "+".green(), entry);
+    }
+    for entry in &report.replaced {
+        eprintln!("  {} {}", "~".yellow(), entry);
+    }
+    if params.set_context {
+        eprintln!(
+            "{} Switched current context to {}",
+            "✓".green(),
+            target.current_context.as_deref().unwrap_or(&prefix).cyan()
+        );
+    } else {
+        eprintln!("  Use it with {}", format!("kubectl --context {}", prefix).cyan());
+    }
+    Ok(())
+}
+
+fn write_kubeconfig(path: &Path, yaml: &str) -> Result<(), Box<dyn std::error::Error>> {
+    secrets::write_private(path, yaml.as_bytes())?;
+    eprintln!(
+        "{} Kubeconfig saved to {}",
+        "✓".green(),
+        path.to_str().unwrap().cyan()
+    );
     Ok(())
 }
 
@@ -57,17 +131,25 @@ pub async fn handle_connect(
     client: &CanineClient,
     id: &ClusterId,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    if gate_telepresence().is_err() {
-        println!(
-            "{} telepresence not found. Install it here: {}",
-            "✗".red(),
-            "https://telepresence.io/docs/install/client".cyan()
-        );
+    if let Err(e) = gate_telepresence() {
+        match e {
+            TelepresenceError::NotFound => println!(
+                "{} telepresence not found. Install it here: {}",
+                "✗".red(),
+                "https://telepresence.io/docs/install/client".cyan()
+            ),
+            TelepresenceError::NotExecutable(e) => {
+                println!("{} telepresence could not be executed: {}", "✗".red(), e)
+            }
+            TelepresenceError::FailedToRun(stderr) => {
+                println!("{} telepresence failed: {}", "✗".red(), stderr.trim())
+            }
+        }
         std::process::exit(1);
     }
     println!("{} telepresence found", "✓".green());
-    let kubeconfig = client.download_kubeconfig_file(&id.cluster).await?;
-    let yaml = kubeconfig_to_yaml(&kubeconfig.kubeconfig)?;
+    let kubeconfig = kubeconfig::download(client, &id.cluster).await?;
+    let yaml = kubeconfig_to_yaml(&kubeconfig)?;
     config.save_kubeconfig(yaml)?;
 
     Command::new("telepresence")
@@ -0,0 +1,43 @@
+use std::io::{self, Write};
+
+use clap::CommandFactory;
+use clap_complete::env::Shells;
+use colored::Colorize;
+
+use crate::cli::{Cli, CompletionCmd, ManCmd};
+
+/// Environment variable the generated scripts set when asking the binary for completions.
+pub const COMPLETE_VAR: &str = "COMPLETE";
+
+pub async fn handle_completion(params: &CompletionCmd) -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Cli::command();
+    let name = cmd.get_name().to_string();
+
+    if params.static_script {
+        let mut script = Vec::new();
+        clap_complete::aot::generate(params.shell, &mut cmd, name, &mut script);
+        io::stdout().write_all(&script)?;
+        return Ok(());
+    }
+
+    let shell = params.shell.to_string();
+    let shells = Shells::builtins();
+    let completer = shells
+        .completer(&shell)
+        .ok_or_else(|| format!("Unsupported shell {}", shell))?;
+    completer.write_registration(COMPLETE_VAR, &name, &name, &name, &mut io::stdout())?;
+    Ok(())
+}
+
+pub async fn handle_man(params: &ManCmd) -> Result<(), Box<dyn std::error::Error>> {
+    let cmd = Cli::command();
+    match &params.dir {
+        Some(dir) => {
+            std::fs::create_dir_all(dir)?;
+            clap_mangen::generate_to(cmd, dir)?;
+            eprintln!("{} Man pages written to {}", "✓".green(), dir.display().to_string().cyan());
+        }
+        None => clap_mangen::Man::new(cmd).render(&mut io::stdout())?,
+    }
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use colored::Colorize;
+use serde::Serialize;
+use tabled::Tabled;
+
+use crate::cli::ProfileName;
+use crate::config::{CanineConfig, ConfigFile};
+use crate::output::{self, Columns, OutputFormat};
+
+#[derive(Debug, Serialize, Tabled)]
+pub struct ProfileRow {
+    pub current: String,
+    pub name: String,
+    pub host: String,
+    pub account: String,
+}
+
+impl Columns for ProfileRow {}
+
+pub async fn handle_use_profile(name: &ProfileName) -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = ConfigFile::load();
+    if !file.profiles.contains_key(&name.name) {
+        return Err(format!("Profile {} not found", name.name).into());
+    }
+    file.current_profile = Some(name.name.clone());
+    file.save()?;
+    println!("{} Switched to profile {}", "✓".green(), name.name.cyan());
+    Ok(())
+}
+
+pub async fn handle_get_profiles(format: OutputFormat) -> Result<(), Box<dyn std::error::Error>> {
+    let file = ConfigFile::load();
+    let rows: Vec<ProfileRow> = file
+        .profiles
+        .iter()
+        .map(|(name, profile)| ProfileRow {
+            current: if name == file.current_profile() { "*".to_string() } else { String::new() },
+            name: name.clone(),
+            host: profile
+                .host
+                .clone()
+                .unwrap_or_else(|| CanineConfig::DEFAULT_HOST.to_string()),
+            account: profile.account.clone().unwrap_or_default(),
+        })
+        .collect();
+    output::print_list(&rows, format)
+}
+
+pub async fn handle_delete_profile(name: &ProfileName) -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = ConfigFile::load();
+    if file.remove_profile(&name.name, &CanineConfig::secrets()).is_none() {
+        return Err(format!("Profile {} not found", name.name).into());
+    }
+    file.save()?;
+    println!("{} Deleted profile {}", "✓".green(), name.name.cyan());
+    Ok(())
+}
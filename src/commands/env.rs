@@ -0,0 +1,291 @@
+use std::io::{self, Read};
+
+use colored::Colorize;
+
+use crate::cli::{EnvExport, EnvGet, EnvImport, EnvList, EnvSet, EnvUnset};
+use crate::client::{CanineClient, EnvironmentVariable, EnvironmentVariableUpdate};
+use crate::output::{self, OutputFormat};
+
+const MASK: &str = "********";
+
+pub async fn handle_list(
+    client: &CanineClient,
+    params: &EnvList,
+    format: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let variables = client.get_environment_variables(&params.project).await?.environment_variables;
+    output::print_list(&mask(variables, params.reveal), format)
+}
+
+pub async fn handle_get(
+    client: &CanineClient,
+    params: &EnvGet,
+    format: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let variable = client
+        .get_environment_variables(&params.project)
+        .await?
+        .environment_variables
+        .into_iter()
+        .find(|v| v.name == params.name)
+        .ok_or_else(|| format!("{} is not set on project {}", params.name, params.project))?;
+    let variable = mask(vec![variable], params.reveal).remove(0);
+    output::print_item(&variable, format, |v| println!("{}", v.value))
+}
+
+pub async fn handle_set(client: &CanineClient, params: &EnvSet) -> Result<(), Box<dyn std::error::Error>> {
+    let updates = params
+        .variables
+        .iter()
+        .map(|(name, value)| EnvironmentVariableUpdate::new(name, value, params.secret.then_some(true)))
+        .collect();
+    set(client, &params.project, updates, params.redeploy).await
+}
+
+pub async fn handle_unset(client: &CanineClient, params: &EnvUnset) -> Result<(), Box<dyn std::error::Error>> {
+    for name in &params.names {
+        client.delete_environment_variable(&params.project, name).await?;
+        eprintln!("{} Unset {}", "✓".green(), name);
+    }
+    if params.redeploy {
+        redeploy(client, &params.project).await?;
+    }
+    Ok(())
+}
+
+pub async fn handle_import(client: &CanineClient, params: &EnvImport) -> Result<(), Box<dyn std::error::Error>> {
+    let contents = if params.file.as_os_str() == "-" {
+        let mut contents = String::new();
+        io::stdin().read_to_string(&mut contents)?;
+        contents
+    } else {
+        std::fs::read_to_string(&params.file)
+            .map_err(|e| format!("Could not read {}: {}", params.file.display(), e))?
+    };
+
+    let updates: Vec<_> = parse_dotenv(&contents)?
+        .into_iter()
+        .map(|(name, value)| EnvironmentVariableUpdate::new(name, value, params.secret.then_some(true)))
+        .collect();
+    if updates.is_empty() {
+        return Err(format!("No variables found in {}", params.file.display()).into());
+    }
+    set(client, &params.project, updates, params.redeploy).await
+}
+
+pub async fn handle_export(client: &CanineClient, params: &EnvExport) -> Result<(), Box<dyn std::error::Error>> {
+    let variables = client.get_environment_variables(&params.project).await?.environment_variables;
+
+    let mut dotenv = String::new();
+    let mut skipped = 0;
+    for variable in &variables {
+        if variable.secret && !params.reveal {
+            dotenv.push_str(&format!("# {} is secret, pass --reveal to export it\n", variable.name));
+            skipped += 1;
+        } else {
+            dotenv.push_str(&format!("{}={}\n", variable.name, quote(&variable.value)));
+        }
+    }
+
+    match &params.file {
+        Some(path) => {
+            // Exports may hold secrets, so they are only readable by the current user.
+            crate::secrets::write_private(path, dotenv.as_bytes())?;
+            eprintln!("{} Wrote {} variables to {}", "✓".green(), variables.len() - skipped, path.display());
+        }
+        None => print!("{}", dotenv),
+    }
+    if skipped > 0 {
+        eprintln!("{} Left out {} secret values, pass --reveal to include them", "!".yellow(), skipped);
+    }
+    Ok(())
+}
+
+async fn set(
+    client: &CanineClient,
+    project: &str,
+    updates: Vec<EnvironmentVariableUpdate>,
+    redeploy_after: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let names: Vec<_> = updates.iter().map(|u| u.name.clone()).collect();
+    client.set_environment_variables(project, updates).await?;
+    eprintln!("{} Set {}", "✓".green(), names.join(", "));
+    if redeploy_after {
+        redeploy(client, project).await?;
+    }
+    Ok(())
+}
+
+/// Rolls out the current build again so processes start with the new environment.
+async fn redeploy(client: &CanineClient, project: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let result = client.deploy_project(project, true, None).await?;
+    eprintln!("{} {}", "✓".green(), result.message);
+    Ok(())
+}
+
+fn mask(variables: Vec<EnvironmentVariable>, reveal: bool) -> Vec<EnvironmentVariable> {
+    variables
+        .into_iter()
+        .map(|mut v| {
+            if v.secret && !reveal {
+                v.value = MASK.to_string();
+            }
+            v
+        })
+        .collect()
+}
+
+/// Parses `NAME=value` lines, accepting the usual dotenv extras: comments,
+/// `export` prefixes, and single or double quoted values.
+fn parse_dotenv(contents: &str) -> Result<Vec<(String, String)>, String> {
+    let mut variables = Vec::new();
+    let mut lines = contents.lines().enumerate();
+    while let Some((number, line)) = lines.next() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (name, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("line {}: expected NAME=VALUE", number + 1))?;
+        let name = name.trim().to_string();
+        let value = value.trim();
+
+        let value = if let Some(rest) = value.strip_prefix('"') {
+            // Double quoted values may span lines and use escapes.
+            let mut raw = rest.to_string();
+            let end = loop {
+                if let Some(end) = closing_quote(&raw) {
+                    break end;
+                }
+                let (_, next) = lines
+                    .next()
+                    .ok_or_else(|| format!("line {}: unterminated quote", number + 1))?;
+                raw.push('\n');
+                raw.push_str(next);
+            };
+            unescape(&raw[..end])
+        } else if let Some(rest) = value.strip_prefix('\'') {
+            // Single quoted values are literal and cannot contain a quote, so
+            // the first one closes the value, even with a comment after it.
+            rest.find('\'')
+                .map(|end| &rest[..end])
+                .ok_or_else(|| format!("line {}: unterminated quote", number + 1))?
+                .to_string()
+        } else {
+            match value.find(" #") {
+                Some(comment) => value[..comment].trim_end().to_string(),
+                None => value.to_string(),
+            }
+        };
+        variables.push((name, value));
+    }
+    Ok(variables)
+}
+
+/// Position of the quote closing a double quoted value (given without its opening quote).
+fn closing_quote(raw: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in raw.char_indices() {
+        match c {
+            '\\' if !escaped => escaped = true,
+            '"' if !escaped => return Some(i),
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+fn unescape(value: &str) -> String {
+    let mut out = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('t') => out.push('\t'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Quotes a value when it would not survive a round trip through `parse_dotenv` as is.
+fn quote(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:@,+%".contains(c));
+    if plain {
+        return value.to_string();
+    }
+    let mut quoted = String::from('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_dotenv_files() {
+        let contents = r#"
+# database
+export DATABASE_URL=postgres://db/app
+RAILS_ENV = production # inline comment
+GREETING="hello \"world\"\nbye" # "quoted" comment
+LITERAL='no $expansion # here'
+QUOTED_COMMENT='value' # it's quoted
+MULTILINE="line one
+line two"
+EMPTY=
+"#;
+        let variables = parse_dotenv(contents).unwrap();
+        let get = |name: &str| variables.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str());
+
+        assert_eq!(variables.len(), 7);
+        assert_eq!(get("DATABASE_URL"), Some("postgres://db/app"));
+        assert_eq!(get("RAILS_ENV"), Some("production"));
+        assert_eq!(get("GREETING"), Some("hello \"world\"\nbye"));
+        assert_eq!(get("LITERAL"), Some("no $expansion # here"));
+        assert_eq!(get("QUOTED_COMMENT"), Some("value"));
+        assert_eq!(get("MULTILINE"), Some("line one\nline two"));
+        assert_eq!(get("EMPTY"), Some(""));
+    }
+
+    #[test]
+    fn exported_values_parse_back() {
+        let values = ["plain", "with space", "quote\"and\\slash", "multi\nline", "", "a # b"];
+        let dotenv: String = values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| format!("V{}={}\n", i, quote(v)))
+            .collect();
+
+        let parsed = parse_dotenv(&dotenv).unwrap();
+        let parsed: Vec<_> = parsed.iter().map(|(_, v)| v.as_str()).collect();
+        assert_eq!(parsed, values);
+    }
+
+    #[test]
+    fn rejects_lines_without_assignment() {
+        assert_eq!(parse_dotenv("FOO=1\nBAR\n").unwrap_err(), "line 2: expected NAME=VALUE");
+    }
+}
@@ -0,0 +1,96 @@
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+
+use colored::Colorize;
+use futures_util::TryStreamExt;
+
+use crate::cli::InitCmd;
+use crate::client::{CanineClient, Project};
+use crate::config::{CanineConfig, Profile};
+use crate::workspace::{self, Workspace};
+
+pub async fn handle_init(
+    client: &CanineClient,
+    credentials: &Profile,
+    params: &InitCmd,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let path = Path::new(workspace::FILE_NAME);
+    if path.exists() && !params.force {
+        return Err(format!("{} already exists, pass --force to replace it", workspace::FILE_NAME).into());
+    }
+
+    let projects: Vec<_> = client.projects().stream().try_collect().await?;
+    let project = match &params.project {
+        Some(name) => projects
+            .iter()
+            .find(|p| &p.name == name)
+            .ok_or_else(|| format!("Project {} not found", name))?,
+        None => choose(&projects, workspace::git_remote().as_deref())?,
+    };
+
+    let workspace = Workspace {
+        project: Some(project.name.clone()),
+        account: credentials.account.clone(),
+        host: credentials
+            .host
+            .clone()
+            .filter(|host| host != CanineConfig::DEFAULT_HOST),
+        path: None,
+    };
+    std::fs::write(path, serde_yaml::to_string(&workspace)?)?;
+    eprintln!(
+        "{} Wrote {}, commands run from here now default to {}",
+        "✓".green(),
+        workspace::FILE_NAME,
+        project.name.cyan()
+    );
+    Ok(())
+}
+
+/// Asks which project to use, suggesting the one built from `remote`.
+fn choose<'a>(projects: &'a [Project], remote: Option<&str>) -> Result<&'a Project, Box<dyn std::error::Error>> {
+    if projects.is_empty() {
+        return Err("No projects found in this account".into());
+    }
+    let suggested = remote.and_then(|remote| {
+        projects
+            .iter()
+            .position(|p| workspace::same_repository(&p.repository_url, remote))
+    });
+
+    if !io::stdin().is_terminal() {
+        return match suggested {
+            Some(i) => Ok(&projects[i]),
+            None => Err("Could not tell which project this is, pass --project".into()),
+        };
+    }
+
+    for (i, project) in projects.iter().enumerate() {
+        eprintln!("  {:>2}. {}  {}", i + 1, project.name.cyan(), project.repository_url.dimmed());
+    }
+    loop {
+        match suggested {
+            Some(i) => eprint!("Project [{}]: ", i + 1),
+            None => eprint!("Project: "),
+        }
+        io::stderr().flush()?;
+        let mut answer = String::new();
+        if io::stdin().read_line(&mut answer)? == 0 {
+            return Err("No project chosen".into());
+        }
+        let answer = answer.trim();
+        if answer.is_empty()
+            && let Some(i) = suggested
+        {
+            return Ok(&projects[i]);
+        }
+        let chosen = match answer.parse::<usize>() {
+            Ok(n) => n.checked_sub(1).and_then(|i| projects.get(i)),
+            Err(_) => projects.iter().find(|p| p.name == answer),
+        };
+        match chosen {
+            Some(project) => return Ok(project),
+            None => eprintln!("{} Enter a number between 1 and {} or a project name", "!".yellow(), projects.len()),
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use std::fs;
+use std::path::Path;
+
+use colored::Colorize;
+
+use crate::cli::KubeconfigLint;
+use crate::client::CanineClient;
+use crate::kubeconfig::{default_kubeconfig_path, lint, parse_kubeconfig_yaml, Finding, Kubeconfig, Severity};
+use crate::output::{self, OutputFormat};
+
+pub fn handle_lint(params: &KubeconfigLint, format: OutputFormat) -> Result<(), Box<dyn std::error::Error>> {
+    let path = params.path.clone().unwrap_or_else(default_kubeconfig_path);
+    let contents = fs::read_to_string(&path).map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    let kubeconfig = parse_kubeconfig_yaml(&contents).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+
+    let findings = lint(&kubeconfig, path.parent());
+    if findings.is_empty() && matches!(format, OutputFormat::Table | OutputFormat::Wide) {
+        eprintln!("{} No problems found in {}", "✓".green(), path.display());
+        return Ok(());
+    }
+    output::print_list(&findings, format)?;
+
+    let errors = findings.iter().filter(|f| f.severity == Severity::Error).count();
+    if errors > 0 {
+        return Err(format!("{} problems found in {}", errors, path.display()).into());
+    }
+    Ok(())
+}
+
+/// Downloads the credentials for `cluster`, warning about anything kubectl
+/// would trip over.
+pub async fn download(client: &CanineClient, cluster: &str) -> Result<Kubeconfig, Box<dyn std::error::Error>> {
+    let kubeconfig = client.download_kubeconfig_file(cluster).await?.kubeconfig;
+    warn_downloaded(&kubeconfig, cluster);
+    Ok(kubeconfig)
+}
+
+/// Prints what [`lint`] finds in the credentials downloaded for `cluster`.
+pub fn warn_downloaded(kubeconfig: &Kubeconfig, cluster: &str) {
+    warn(lint(kubeconfig, None), &format!("Kubeconfig for {}", cluster.cyan()));
+}
+
+/// Prints what [`lint`] finds in the kubeconfig at `path`.
+pub fn warn_file(kubeconfig: &Kubeconfig, path: &Path) {
+    warn(lint(kubeconfig, path.parent()), &path.display().to_string());
+}
+
+fn warn(findings: Vec<Finding>, source: &str) {
+    for finding in findings {
+        eprintln!("{} {}: {}: {}", "!".yellow(), source, finding.location, finding.message);
+    }
+}
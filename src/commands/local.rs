@@ -117,12 +117,20 @@ async fn download_docker_compose() -> Result<(), Box<dyn std::error::Error>> {
 }
 
 pub async fn handle_start(port: u16) -> Result<(), Box<dyn std::error::Error>> {
-    if check_docker_compose().is_err() {
-        println!(
-            "{} Docker Compose not found. Install Docker Desktop: {}",
-            "✗".red(),
-            "https://docs.docker.com/compose/install/".cyan()
-        );
+    if let Err(e) = check_docker_compose() {
+        match e {
+            DockerComposeError::NotFound => println!(
+                "{} Docker Compose not found. Install Docker Desktop: {}",
+                "✗".red(),
+                "https://docs.docker.com/compose/install/".cyan()
+            ),
+            DockerComposeError::NotExecutable(e) => {
+                println!("{} Docker could not be executed: {}", "✗".red(), e)
+            }
+            DockerComposeError::FailedToRun(stderr) => {
+                println!("{} Docker Compose failed: {}", "✗".red(), stderr.trim())
+            }
+        }
         std::process::exit(1);
     }
     println!("{} Docker Compose found", "✓".green());
@@ -1,7 +1,25 @@
 pub mod account;
 pub mod add_on;
+pub mod apply;
 pub mod auth;
 pub mod build;
 pub mod cluster;
+pub mod completion;
+pub mod config;
+pub mod env;
+pub mod init;
+pub mod kubeconfig;
 pub mod local;
-pub mod project;
\ No newline at end of file
+pub mod project;
+
+/// Client for a wiremock server. The library has the same helper in
+/// `client`, but its `cfg(test)` items are not built for the binary's tests.
+#[cfg(test)]
+pub(crate) fn mock_client(server: &wiremock::MockServer) -> crate::client::CanineClient {
+    crate::client::CanineClient::builder()
+        .host(server.uri())
+        .api_key("token")
+        .retry_backoff(std::time::Duration::from_millis(1))
+        .build()
+        .unwrap()
+}
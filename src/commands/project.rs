@@ -1,130 +1,765 @@
-use std::io::{self, Write};
-use std::process::{Command, Stdio};
-use std::thread::sleep;
+use std::collections::{BTreeMap, HashSet};
+use std::io::{self, IsTerminal};
+use std::process::Stdio;
 use std::time::Duration;
 
-use colored::Colorize;
-use tabled::Table;
+use colored::{Color, Colorize};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use tokio::time::Instant;
 
-use crate::cli::{DeployProjectParams, ProjectId, ProjectRun};
-use crate::client::{CanineClient, CanineError, Pod, ProcessStatus};
+use crate::cli::{
+    DeployProjectParams, OneOffPrune, ProjectCreate, ProjectDelete, ProjectId, ProjectList, ProjectLogs, ProjectProcesses, ProjectRestart, ProjectRollback,
+    ProjectRun, ProjectScale, ProjectSettings, ProjectUpdate,
+};
+use crate::client::{
+    CanineClient, CanineError, Deployment, DeploymentStatus, Pod, Process, ProcessStatus, ProjectSpec,
+};
+use crate::commands::{build, kubeconfig};
 use crate::config::CanineConfig;
-use crate::kubeconfig::{ensure_kubectl, kubeconfig_to_yaml};
+use crate::exec::{self, ExecOptions};
+use crate::kubeconfig::{ensure_kubectl, kubeconfig_to_yaml, Kubeconfig, KubectlError};
+use crate::output::{self, OutputFormat};
 
-pub async fn handle_list(client: &CanineClient) -> Result<(), Box<dyn std::error::Error>> {
-    let projects = client.get_projects().await?.projects;
-    println!("{}", Table::new(projects));
+pub async fn handle_list(
+    client: &CanineClient,
+    params: &ProjectList,
+    format: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let projects = output::fetch(&params.pages, &params.list, client.projects()).await?;
+    output::print_selected(projects, format, &params.list)
+}
+
+pub async fn handle_create(
+    client: &CanineClient,
+    params: &ProjectCreate,
+    format: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut spec = settings_spec(&params.settings);
+    spec.name = Some(params.name.clone());
+    spec.repository_url = Some(params.repository_url.clone());
+    spec.cluster_name = Some(params.cluster.clone());
+    let project = client.create_project(spec).await?;
+    output::print_item(&project, format, |project| {
+        println!("{} Created project {}", "✓".green(), project.name.cyan());
+    })
+}
+
+pub async fn handle_update(
+    client: &CanineClient,
+    params: &ProjectUpdate,
+    format: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut spec = settings_spec(&params.settings);
+    spec.repository_url = params.repository_url.clone();
+    if spec == ProjectSpec::default() {
+        return Err("Nothing to update, pass at least one setting".into());
+    }
+    let project = client.update_project(&params.project, spec).await?;
+    output::print_item(&project, format, |project| {
+        println!("{} Updated project {}", "✓".green(), project.name.cyan());
+    })
+}
+
+pub async fn handle_delete(client: &CanineClient, params: &ProjectDelete) -> Result<(), Box<dyn std::error::Error>> {
+    if !params.yes {
+        let prompt = format!(
+            "{} This deletes project {} along with its processes and deployment history.",
+            "!".yellow(),
+            params.project
+        );
+        if !output::confirm(&prompt, &params.project)? {
+            return Err("Aborted, project was not deleted".into());
+        }
+    }
+    client.delete_project(&params.project).await?;
+    eprintln!("{} Deleted project {}", "✓".green(), params.project);
     Ok(())
 }
 
+fn settings_spec(settings: &ProjectSettings) -> ProjectSpec {
+    let mut spec = ProjectSpec::default();
+    spec.branch = settings.branch.clone();
+    spec.dockerfile_path = settings.dockerfile_path.clone();
+    spec.build_context = settings.build_context.clone();
+    spec.autodeploy = settings.autodeploy;
+    spec
+}
+
 pub async fn handle_processes(
+    client: &CanineClient,
+    params: &ProjectProcesses,
+    format: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if params.watch.watch {
+        return output::watch(&params.watch, &params.list, format, || async {
+            Ok(client.get_processes(&params.project).await?.pods)
+        })
+        .await;
+    }
+    let processes = client.get_processes(&params.project).await?;
+    output::print_selected(processes.pods, format, &params.list)
+}
+
+pub async fn handle_run(
+    client: &CanineClient,
+    project_name: &str,
+    params: &ProjectRun,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    // Progress goes to stderr so the command's own output can be piped.
+    eprint!("Fetching project {}... ", project_name.cyan());
+    let project = client.get_project(project_name).await?;
+    eprintln!("{}", "done".green());
+
+    eprint!("Fetching credentials for cluster {}... ", project.cluster_name.cyan());
+    // Kept in memory only, the exec stream is opened without kubectl.
+    let kubeconfig = client.download_kubeconfig_file(&project.cluster_name).await?.kubeconfig;
+    eprintln!("{}", "done".green());
+    kubeconfig::warn_downloaded(&kubeconfig, &project.cluster_name);
+
+    eprint!("Starting one-off container in {}... ", project.name.cyan());
+
+    let pod = client.create_one_off_pod(project_name).await?;
+    eprintln!("{}", "done".green());
+    eprintln!("  Pod: {}", pod.name.dimmed());
+
+    let session = async {
+        wait_pod_ready(
+            client,
+            project_name,
+            &pod.name,
+            Duration::from_secs(params.ready_timeout),
+            Duration::from_millis(params.poll_interval),
+        )
+        .await?;
+        exec(&kubeconfig, &pod, params).await
+    };
+    // Dropping the session on a signal closes the exec stream, so the pod can be cleaned up.
+    let result = tokio::select! {
+        result = session => result,
+        code = interrupted() => Ok(code),
+    };
+
+    if params.keep {
+        eprintln!("{} Keeping pod {}", "→".cyan(), pod.name.cyan());
+    } else {
+        delete_one_off(client, project_name, &pod.name).await;
+    }
+    result
+}
+
+async fn exec(
+    kubeconfig: &Kubeconfig,
+    pod: &Pod,
+    params: &ProjectRun,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    let options = ExecOptions {
+        command: params.command.clone(),
+        container: None,
+        stdin: params.interactive,
+        tty: allocate_tty(params),
+    };
+    Ok(exec::exec_attached(kubeconfig, &pod.namespace, &pod.name, &options).await?)
+}
+
+/// Resolves with the conventional exit code once the process is asked to stop.
+async fn interrupted() -> i32 {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+        let (Ok(mut term), Ok(mut hangup)) = (signal(SignalKind::terminate()), signal(SignalKind::hangup())) else {
+            let _ = tokio::signal::ctrl_c().await;
+            return 130;
+        };
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => 130,
+            _ = term.recv() => 143,
+            _ = hangup.recv() => 129,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        130
+    }
+}
+
+async fn delete_one_off(client: &CanineClient, project_id: &str, pod_id: &str) {
+    match client.delete_pod(project_id, pod_id).await {
+        Ok(()) => eprintln!("{} Deleted pod {}", "✓".green(), pod_id.dimmed()),
+        Err(e) => eprintln!(
+            "{} Could not delete pod {}: {}. Remove it with `canine projects one-offs prune --project {} --running`",
+            "!".yellow(),
+            pod_id,
+            e,
+            project_id
+        ),
+    }
+}
+
+pub async fn handle_one_offs(
     client: &CanineClient,
     id: &ProjectId,
+    format: OutputFormat,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let processes = client.get_processes(&id.project).await?;
-    println!("{}", Table::new(processes.pods));
+    let pods = client.get_one_off_pods(&id.project).await?;
+    output::print_list(&pods.pods, format)
+}
+
+pub async fn handle_prune_one_offs(
+    client: &CanineClient,
+    params: &OneOffPrune,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let pods: Vec<_> = client
+        .get_one_off_pods(&params.project)
+        .await?
+        .pods
+        .into_iter()
+        .filter(|pod| params.running || !in_use(pod))
+        .collect();
+    if pods.is_empty() {
+        eprintln!("{} No one-off pods to prune", "✓".green());
+        return Ok(());
+    }
+
+    if !params.yes {
+        for pod in &pods {
+            eprintln!("  {} ({})", pod.name, pod.status);
+        }
+        let prompt = format!("{} This deletes {} one-off pods of {}.", "!".yellow(), pods.len(), params.project);
+        if !output::confirm(&prompt, &params.project)? {
+            return Err("Aborted, no pods were deleted".into());
+        }
+    }
+    for pod in &pods {
+        client.delete_pod(&params.project, &pod.name).await?;
+        eprintln!("{} Deleted pod {} ({})", "✓".green(), pod.name, pod.status);
+    }
     Ok(())
 }
 
-pub async fn handle_run(
+/// Pending and running one-off pods may be sessions someone is still using.
+fn in_use(pod: &Process) -> bool {
+    matches!(pod.status, ProcessStatus::Pending | ProcessStatus::Running)
+}
+
+/// A TTY is only useful when both ends of it are a terminal; in CI or with
+/// redirected input it would mangle the stream.
+fn allocate_tty(params: &ProjectRun) -> bool {
+    params.interactive && !params.no_tty && io::stdin().is_terminal() && io::stdout().is_terminal()
+}
+
+const ROLLOUT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+const LOG_COLORS: [Color; 6] = [
+    Color::Cyan,
+    Color::Green,
+    Color::Yellow,
+    Color::Magenta,
+    Color::Blue,
+    Color::BrightRed,
+];
+
+pub async fn handle_logs(
     config: &CanineConfig,
     client: &CanineClient,
-    params: &ProjectRun,
+    project_name: &str,
+    params: &ProjectLogs,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    gate_kubectl();
-
-    print!("Fetching project {}... ", params.project.cyan());
-    io::stdout().flush().unwrap();
-    let project = client.get_project(&params.project).await?;
-    println!("{}", "done".green());
-
-    print!("Downloading kubeconfig for cluster {}... ", project.cluster_name.cyan());
-    io::stdout().flush().unwrap();
-    // Save kubeconfig
-    let kubeconfig = client
-        .download_kubeconfig_file(&project.cluster_name.to_string())
-        .await?;
-    let yaml = kubeconfig_to_yaml(&kubeconfig.kubeconfig)?;
+    gate_kubectl()?;
+
+    let project = client.get_project(project_name).await?;
+    let kubeconfig = kubeconfig::download(client, &project.cluster_name).await?;
+    let yaml = kubeconfig_to_yaml(&kubeconfig)?;
     config.save_kubeconfig(yaml)?;
 
-    print!("Starting one-off container in {}... ", project.name.cyan());
-    io::stdout().flush().unwrap();
-
-    let pod = client.create_one_off_pod(&params.project).await?;
-    println!("{}", "done".green());
-    println!("  Pod: {}", pod.name.dimmed());
-
-    wait_pod_ready(client, &params.project, &pod.name).await?;
-
-    let mut args = vec![
-        "exec".to_string(),
-        "-it".to_string(),
-        "-n".to_string(),
-        pod.namespace,
-        pod.name,
-        "--".to_string(),
-    ];
-    args.extend(params.command.clone());
-
-    Command::new("kubectl")
-        .args(&args)
-        .env(
-            "KUBECONFIG",
-            CanineConfig::credential_path().to_str().unwrap(),
-        )
-        .stdin(Stdio::inherit())
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .status()?;
+    let pods: Vec<_> = client
+        .get_processes(project_name)
+        .await?
+        .pods
+        .into_iter()
+        .filter(|pod| params.process.as_deref().is_none_or(|process| pod.runs(process)))
+        .collect();
 
+    if pods.is_empty() {
+        return Err(format!("No pods found for project {}", project_name).into());
+    }
+
+    let commands = pods
+        .into_iter()
+        .map(|pod| {
+            let mut command = Command::new("kubectl");
+            command
+                .args(["logs", "-n", &pod.namespace, &pod.name, "--all-containers"])
+                .env("KUBECONFIG", CanineConfig::credential_path());
+            if params.follow {
+                command.arg("--follow");
+            }
+            if params.previous {
+                command.arg("--previous");
+            }
+            if let Some(since) = &params.since {
+                command.arg(format!("--since={}", since));
+            }
+            if let Some(tail) = params.tail {
+                command.arg(format!("--tail={}", tail));
+            }
+            (pod.name, command)
+        })
+        .collect();
+    stream_output(commands).await
+}
+
+/// Runs one command per pod side by side, prefixing each line with the pod
+/// name when there is more than one, and fails if any of them fails.
+async fn stream_output(commands: Vec<(String, Command)>) -> Result<(), Box<dyn std::error::Error>> {
+    let prefixed = commands.len() > 1;
+    let mut tasks = Vec::new();
+    for (i, (pod, mut command)) in commands.into_iter().enumerate() {
+        let mut child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let prefix = if prefixed {
+            format!("{} ", pod.color(LOG_COLORS[i % LOG_COLORS.len()]))
+        } else {
+            String::new()
+        };
+        let stdout = child.stdout.take().unwrap();
+        let stderr = child.stderr.take().unwrap();
+        tasks.push(tokio::spawn(async move {
+            let out = async {
+                let mut lines = BufReader::new(stdout).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    println!("{}{}", prefix, line);
+                }
+            };
+            let err = async {
+                let mut lines = BufReader::new(stderr).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    eprintln!("{}{}", prefix, line.red());
+                }
+            };
+            tokio::join!(out, err);
+            (pod, child.wait().await)
+        }));
+    }
+
+    let mut failed = Vec::new();
+    for task in tasks {
+        let (pod, status) = task.await?;
+        let status = status?;
+        if !status.success() {
+            failed.push(format!("{} ({})", pod, status));
+        }
+    }
+    if !failed.is_empty() {
+        return Err(format!("kubectl logs failed for {}", failed.join(", ")).into());
+    }
     Ok(())
 }
 
 pub async fn handle_deploy(
     client: &CanineClient,
+    project_name: &str,
     params: &DeployProjectParams,
+    format: OutputFormat,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let result = client
-        .deploy_project(&params.name, params.skip_build)
+        .deploy_project(project_name, params.skip_build, None)
         .await?;
-    let url = format!(
-        "{}/projects/{}/deployments/{}",
-        client.base_url, params.name, result.build_id
-    );
-    println!("{} {}", "✓".green(), result.message);
-    println!("  View deployment: {}", url.blue());
+    output::print_item(&result, format, |result| {
+        let url = format!(
+            "{}/projects/{}/deployments/{}",
+            client.base_url, project_name, result.build_id
+        );
+        println!("{} {}", "✓".green(), result.message);
+        println!("  View deployment: {}", url.blue());
+    })?;
+
+    if params.wait {
+        build::wait_for_deployment(client, result.build_id, Duration::from_secs(params.timeout))
+            .await?;
+    }
     Ok(())
 }
 
-fn gate_kubectl() {
-    if ensure_kubectl().is_err() {
+fn gate_kubectl() -> Result<(), Box<dyn std::error::Error>> {
+    match ensure_kubectl() {
+        Ok(()) => {}
+        Err(KubectlError::NotFound) => {
+            return Err(format!(
+                "kubectl not found. Install it: {}",
+                "https://kubernetes.io/docs/tasks/tools/".cyan()
+            )
+            .into());
+        }
+        Err(e) => return Err(Box::new(e)),
+    }
+    eprintln!("{} kubectl found", "✓".green());
+    Ok(())
+}
+
+pub async fn handle_scale(client: &CanineClient, params: &ProjectScale) -> Result<(), Box<dyn std::error::Error>> {
+    let processes: BTreeMap<_, _> = params.processes.iter().cloned().collect();
+    client.scale_project(&params.project, processes.clone()).await?;
+    let summary: Vec<_> = processes.iter().map(|(p, n)| format!("{}={}", p, n)).collect();
+    eprintln!("{} Scaling {}", "✓".green(), summary.join(" "));
+
+    if params.rollout.no_wait {
+        return Ok(());
+    }
+    let targets = processes
+        .into_iter()
+        .map(|(process, replicas)| Target {
+            process: Some(process),
+            replicas: replicas as usize,
+            old: HashSet::new(),
+        })
+        .collect();
+    wait_for_processes(client, &params.project, targets, Duration::from_secs(params.rollout.timeout)).await
+}
+
+pub async fn handle_restart(client: &CanineClient, params: &ProjectRestart) -> Result<(), Box<dyn std::error::Error>> {
+    // Without --process every pod of the project is replaced.
+    let process = params.process.clone();
+    let before: HashSet<_> = matching(&client.get_processes(&params.project).await?.pods, process.as_deref())
+        .map(|pod| pod.name.clone())
+        .collect();
+
+    client.restart_project(&params.project, params.process.clone()).await?;
+    eprintln!(
+        "{} Restarting {}",
+        "✓".green(),
+        params.process.as_deref().unwrap_or(&params.project)
+    );
+
+    if params.rollout.no_wait {
+        return Ok(());
+    }
+    let target = Target {
+        process,
+        replicas: before.len(),
+        old: before,
+    };
+    wait_for_processes(client, &params.project, vec![target], Duration::from_secs(params.rollout.timeout)).await
+}
+
+/// The state a process should reach before a rollout counts as settled.
+struct Target {
+    /// `None` stands for every process of the project.
+    process: Option<String>,
+    replicas: usize,
+    /// Pods that have to be replaced.
+    old: HashSet<String>,
+}
+
+impl Target {
+    fn ready(&self, pods: &[Process]) -> usize {
+        matching(pods, self.process.as_deref())
+            .filter(|pod| pod.status == ProcessStatus::Running && !self.old.contains(&pod.name))
+            .count()
+    }
+
+    fn settled(&self, pods: &[Process]) -> bool {
+        matching(pods, self.process.as_deref()).count() == self.replicas && self.ready(pods) == self.replicas
+    }
+}
+
+/// Pods that run `process`, or all of them without one.
+fn matching<'a>(pods: &'a [Process], process: Option<&'a str>) -> impl Iterator<Item = &'a Process> {
+    pods.iter().filter(move |pod| process.is_none_or(|process| pod.runs(process)))
+}
+
+async fn wait_for_processes(
+    client: &CanineClient,
+    project: &str,
+    targets: Vec<Target>,
+    timeout: Duration,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let pods = client.get_processes(project).await?.pods;
+        if let Some(failed) = targets
+            .iter()
+            .flat_map(|target| matching(&pods, target.process.as_deref()))
+            .find(|pod| pod.status == ProcessStatus::Failed)
+        {
+            eprintln!();
+            return Err(format!("Pod {} failed during the rollout", failed.name).into());
+        }
+
+        let progress: Vec<_> = targets
+            .iter()
+            .map(|target| {
+                let name = target.process.as_deref().unwrap_or(project);
+                format!("{} {}/{}", name, target.ready(&pods), target.replicas)
+            })
+            .collect();
+        eprint!("\r{} Rolling out: {} ready{:10}", "→".cyan(), progress.join(", "), "");
+
+        if targets.iter().all(|target| target.settled(&pods)) {
+            eprintln!("\r{} Rollout complete: {} ready{:10}", "✓".green(), progress.join(", "), "");
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            eprintln!();
+            return Err(format!("Timed out waiting for {} to roll out", project).into());
+        }
+        tokio::time::sleep(ROLLOUT_POLL_INTERVAL).await;
+    }
+}
+
+pub async fn handle_deployments(
+    client: &CanineClient,
+    id: &ProjectId,
+    format: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let deployments = client.get_deployments(&id.project).await?.deployments;
+    output::print_list(&deployments, format)
+}
+
+pub async fn handle_rollback(
+    client: &CanineClient,
+    params: &ProjectRollback,
+    format: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let history = client.get_deployments(&params.project).await?.deployments;
+    let target = rollback_target(&history, params.to)?;
+    let latest = history.iter().map(|d| d.id).max().unwrap_or(0);
+
+    let result = client
+        .deploy_project(&params.project, true, Some(target.build_id))
+        .await?;
+    output::print_item(&result, format, |result| {
         println!(
-            "{} kubectl not found. Install it: {}",
-            "✗".red(),
-            "https://kubernetes.io/docs/tasks/tools/".cyan()
+            "{} Rolling back to build {} ({})",
+            "✓".green(),
+            result.build_id,
+            target.commit_sha.as_deref().unwrap_or("unknown commit")
         );
-        std::process::exit(1);
+    })?;
+
+    if params.wait {
+        build::wait_for_new_deployment(client, &params.project, latest, Duration::from_secs(params.timeout)).await?;
+    }
+    Ok(())
+}
+
+/// The deployment named by `--to`, or else the newest successful deployment of
+/// a build other than the one currently live.
+fn rollback_target(history: &[Deployment], to: Option<i32>) -> Result<&Deployment, String> {
+    if let Some(id) = to {
+        return history
+            .iter()
+            .find(|d| d.id == id)
+            .ok_or_else(|| format!("Deployment {} not found", id));
     }
-    println!("{} kubectl found", "✓".green());
+
+    let mut completed = history
+        .iter()
+        .filter(|d| d.status == DeploymentStatus::Completed);
+    let current = completed
+        .next()
+        .ok_or("No successful deployment to roll back from")?;
+    completed
+        .find(|d| d.build_id != current.build_id)
+        .ok_or_else(|| "No earlier build to roll back to".to_string())
 }
 
+/// Polls the pod until it is running. Pods that have already stopped will never
+/// become ready, so those fail straight away.
 async fn wait_pod_ready(
     client: &CanineClient,
     project_id: &str,
     pod_id: &str,
+    timeout: Duration,
+    interval: Duration,
 ) -> Result<Pod, CanineError> {
     let frames = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
-    for i in 1..=30 {
-        print!("\r{} Waiting for pod to be ready", frames[i % frames.len()].cyan());
-        io::stdout().flush().unwrap();
-
-        sleep(Duration::from_millis(400));
+    let deadline = Instant::now() + timeout;
+    let mut frame = 0;
+    loop {
         let pod = client.get_pod(project_id, pod_id).await?;
-        if pod.status == ProcessStatus::Running {
-            println!("\r{} Pod ready                      ", "✓".green());
-            return Ok(pod);
+        match pod.status {
+            ProcessStatus::Running => {
+                eprintln!("\r{} Pod ready{:40}", "✓".green(), "");
+                return Ok(pod);
+            }
+            ProcessStatus::Failed | ProcessStatus::Succeeded => {
+                eprintln!("\r{} Pod failed to start{:30}", "✗".red(), "");
+                return Err(CanineError::OneOffPodStopped {
+                    pod: pod.name.clone(),
+                    status: pod.describe(),
+                });
+            }
+            _ => {}
+        }
+        if Instant::now() >= deadline {
+            eprintln!("\r{} Pod failed to start{:30}", "✗".red(), "");
+            return Err(CanineError::OneOffPodTimedOut {
+                pod: pod.name.clone(),
+                seconds: timeout.as_secs(),
+                status: pod.describe(),
+            });
         }
+
+        eprint!(
+            "\r{} Waiting for pod to be ready ({})",
+            frames[frame].cyan(),
+            pod.describe().dimmed()
+        );
+        frame = (frame + 1) % frames.len();
+        tokio::time::sleep(interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::mock_client;
+    use serde_json::json;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn mock_pod(server: &MockServer, pod: serde_json::Value) {
+        Mock::given(method("GET"))
+            .and(path("/api/v1/projects/api/processes/api-run-1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(pod))
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn fails_fast_when_the_pod_stops() {
+        let server = MockServer::start().await;
+        mock_pod(&server, json!({
+            "name": "api-run-1",
+            "namespace": "api",
+            "status": "Failed",
+            "reason": "ErrImagePull",
+            "message": "image not found",
+        }))
+        .await;
+
+        let err = wait_pod_ready(&mock_client(&server), "api", "api-run-1", Duration::from_secs(60), Duration::from_millis(10))
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "one-off pod api-run-1 stopped before it was ready: Failed: ErrImagePull (image not found)"
+        );
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn reports_last_status_on_timeout() {
+        let server = MockServer::start().await;
+        mock_pod(&server, json!({
+            "name": "api-run-1",
+            "namespace": "api",
+            "status": "Pending",
+            "reason": "ContainerCreating",
+        }))
+        .await;
+
+        let err = wait_pod_ready(&mock_client(&server), "api", "api-run-1", Duration::ZERO, Duration::from_millis(10))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, CanineError::OneOffPodTimedOut { ref status, .. } if status == "Pending: ContainerCreating"));
+    }
+
+    fn process(name: &str, status: ProcessStatus) -> Process {
+        serde_json::from_value(json!({ "name": name, "namespace": "api", "status": status })).unwrap()
+    }
+
+    #[tokio::test]
+    async fn log_streams_fail_when_kubectl_fails() {
+        let sh = |script: &str| {
+            let mut command = Command::new("sh");
+            command.args(["-c", script]);
+            command
+        };
+
+        assert!(stream_output(vec![("api-web-1".to_string(), sh("echo ok"))]).await.is_ok());
+
+        let err = stream_output(vec![
+            ("api-web-1".to_string(), sh("echo ok")),
+            ("api-web-2".to_string(), sh("echo 'pod not found' >&2; exit 1")),
+        ])
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("api-web-2"));
+        assert!(!err.to_string().contains("api-web-1"));
+    }
+
+    #[test]
+    fn rollout_settles_once_old_pods_are_replaced() {
+        let target = Target {
+            process: Some("web".to_string()),
+            replicas: 2,
+            old: HashSet::from(["api-web-5d8f9-a1".to_string(), "api-web-5d8f9-a2".to_string()]),
+        };
+
+        let rolling = [
+            process("api-web-5d8f9-a1", ProcessStatus::Running),
+            process("api-web-7c4b2-b3", ProcessStatus::Running),
+            process("api-web-7c4b2-b4", ProcessStatus::Pending),
+            process("api-worker-6f7d1-c1", ProcessStatus::Running),
+        ];
+        assert_eq!(target.ready(&rolling), 1);
+        assert!(!target.settled(&rolling));
+
+        let done = [
+            process("api-web-7c4b2-b3", ProcessStatus::Running),
+            process("api-web-7c4b2-b4", ProcessStatus::Running),
+            process("api-worker-6f7d1-c1", ProcessStatus::Running),
+        ];
+        assert!(target.settled(&done));
+    }
+
+    #[test]
+    fn matches_processes_by_deployment_name() {
+        let pods = [
+            process("web-web-5d8f9-a1", ProcessStatus::Running),
+            process("web-webhooks-7c4b2-b1", ProcessStatus::Running),
+            process("web-worker-6f7d1-c1", ProcessStatus::Running),
+        ];
+        let names = |process| matching(&pods, process).map(|pod| pod.name.as_str()).collect::<Vec<_>>();
+
+        assert_eq!(names(Some("web")), ["web-web-5d8f9-a1"]);
+        assert_eq!(names(Some("webhooks")), ["web-webhooks-7c4b2-b1"]);
+        assert_eq!(names(Some("hooks")), Vec::<&str>::new());
+        assert_eq!(names(None).len(), 3);
+    }
+
+    fn deployment(id: i32, build_id: i32, status: DeploymentStatus) -> Deployment {
+        serde_json::from_value(json!({
+            "id": id,
+            "build_id": build_id,
+            "status": status,
+            "created_at": chrono::Utc::now(),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn rolls_back_to_the_previous_successful_build() {
+        let history = [
+            deployment(5, 30, DeploymentStatus::Failed),
+            deployment(4, 20, DeploymentStatus::Completed),
+            deployment(3, 20, DeploymentStatus::Completed),
+            deployment(2, 15, DeploymentStatus::Failed),
+            deployment(1, 10, DeploymentStatus::Completed),
+        ];
+
+        assert_eq!(rollback_target(&history, None).unwrap().id, 1);
+        assert_eq!(rollback_target(&history, Some(2)).unwrap().build_id, 15);
+        assert!(rollback_target(&history, Some(9)).is_err());
+        assert!(rollback_target(&history[..3], None).is_err());
     }
-    println!("\r{} Pod failed to start            ", "✗".red());
-    Err(CanineError::OneOffPodNeverReady)
 }
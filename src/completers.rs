@@ -0,0 +1,235 @@
+//! Dynamic shell completion of project, cluster, build and add-on identifiers.
+//!
+//! Completers run on every <TAB>, so results are cached on disk for a short
+//! while. Any failure (no credentials, no network) simply yields no candidates.
+
+use std::ffi::OsStr;
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use clap_complete::engine::CompletionCandidate;
+use futures_util::TryStreamExt;
+use serde::{Deserialize, Serialize};
+
+use crate::client::{AddOn, Auth, Build, CanineClient, ClientOptions, Cluster, Project};
+use crate::config::{CanineConfig, Profile};
+use crate::workspace::Workspace;
+
+const CACHE_TTL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Candidate {
+    value: String,
+    help: Option<String>,
+}
+
+pub fn projects(current: &OsStr) -> Vec<CompletionCandidate> {
+    complete(current, "projects", |client| async move {
+        let projects: Vec<Project> = client.projects().stream().try_collect().await?;
+        Ok(projects
+            .into_iter()
+            .map(|p| Candidate {
+                value: p.name,
+                help: Some(p.cluster_name),
+            })
+            .collect())
+    })
+}
+
+pub fn clusters(current: &OsStr) -> Vec<CompletionCandidate> {
+    complete(current, "clusters", |client| async move {
+        let clusters: Vec<Cluster> = client.clusters().stream().try_collect().await?;
+        Ok(clusters
+            .into_iter()
+            .map(|c| Candidate {
+                value: c.name,
+                help: Some(c.status.to_string()),
+            })
+            .collect())
+    })
+}
+
+pub fn builds(current: &OsStr) -> Vec<CompletionCandidate> {
+    complete(current, "builds", |client| async move {
+        let builds: Vec<Build> = client.builds().stream().try_collect().await?;
+        Ok(builds
+            .into_iter()
+            .map(|b| Candidate {
+                value: b.id.to_string(),
+                help: Some(format!("{} {}", b.project_slug, b.commit_message)),
+            })
+            .collect())
+    })
+}
+
+pub fn add_ons(current: &OsStr) -> Vec<CompletionCandidate> {
+    complete(current, "add_ons", |client| async move {
+        let add_ons: Vec<AddOn> = client.add_ons().stream().try_collect().await?;
+        Ok(add_ons
+            .into_iter()
+            .map(|a| Candidate {
+                value: a.name,
+                help: Some(a.cluster_name),
+            })
+            .collect())
+    })
+}
+
+fn complete<F, Fut>(current: &OsStr, kind: &str, fetch: F) -> Vec<CompletionCandidate>
+where
+    F: FnOnce(CanineClient) -> Fut + Send,
+    Fut: Future<Output = Result<Vec<Candidate>, Box<dyn std::error::Error>>>,
+{
+    let current = current.to_string_lossy();
+    // The shell passes the command line being completed after `--`.
+    let words: Vec<String> = std::env::args_os()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .skip_while(|arg| arg != "--")
+        .skip(1)
+        .collect();
+    let option = |name: &str, env: &str| flag_value(&words, name).or_else(|| std::env::var(env).ok());
+
+    let profile = option("profile", "CANINE_PROFILE");
+    // Without a home directory there is no config file, only flags and environment variables.
+    let config = match dirs::home_dir() {
+        Some(_) => CanineConfig::load(profile.as_deref()),
+        None => CanineConfig {
+            profile: profile.unwrap_or_else(|| CanineConfig::DEFAULT_PROFILE.to_string()),
+            host: None,
+            token: None,
+            account: None,
+        },
+    };
+    let overrides = Profile {
+        host: option("host", "CANINE_HOST"),
+        token: option("token", "CANINE_TOKEN"),
+        token_ref: None,
+        account: option("account", "CANINE_ACCOUNT"),
+    };
+    let credentials = config.resolve(&overrides.or(Workspace::discover().defaults()));
+    let path = cache_path(&config.profile, &credentials, kind);
+
+    let candidates = match path.as_deref().and_then(read_cache) {
+        Some(candidates) => candidates,
+        None => {
+            let Some(client) = client(credentials) else {
+                return Vec::new();
+            };
+            // The completer is called from inside the main runtime, so the request
+            // gets a runtime of its own on a separate thread.
+            let fetched = std::thread::scope(|scope| {
+                scope
+                    .spawn(|| {
+                        let runtime = tokio::runtime::Builder::new_current_thread()
+                            .enable_all()
+                            .build()
+                            .ok()?;
+                        runtime.block_on(fetch(client)).ok()
+                    })
+                    .join()
+                    .ok()
+                    .flatten()
+            });
+            let Some(candidates) = fetched else {
+                return Vec::new();
+            };
+            if let Some(path) = &path {
+                write_cache(path, &candidates);
+            }
+            candidates
+        }
+    };
+
+    candidates
+        .into_iter()
+        .filter(|c| c.value.starts_with(current.as_ref()))
+        .map(|c| CompletionCandidate::new(c.value).help(c.help.map(Into::into)))
+        .collect()
+}
+
+/// The value given to `--name` on the command line, as `--name value` or `--name=value`.
+fn flag_value(words: &[String], name: &str) -> Option<String> {
+    let flag = format!("--{}", name);
+    let prefix = format!("{}=", flag);
+    words.iter().enumerate().rev().find_map(|(i, word)| {
+        if *word == flag {
+            words.get(i + 1).cloned()
+        } else {
+            word.strip_prefix(&prefix).map(str::to_string)
+        }
+    })
+}
+
+fn client(credentials: Profile) -> Option<CanineClient> {
+    let mut options = ClientOptions::default();
+    options.connect_timeout = Duration::from_secs(2);
+    options.read_timeout = Duration::from_secs(5);
+    options.max_retries = 0;
+    CanineClient::with_options(
+        credentials
+            .host
+            .unwrap_or_else(|| CanineConfig::DEFAULT_HOST.to_string()),
+        Auth::ApiKey(credentials.token?),
+        credentials.account,
+        options,
+    )
+    .ok()
+}
+
+/// Results are cached per profile, host and account, so overriding one of
+/// them on the command line does not complete names from another. Without a
+/// home directory nothing is cached.
+fn cache_path(profile: &str, credentials: &Profile, kind: &str) -> Option<PathBuf> {
+    let mut hasher = DefaultHasher::new();
+    (&credentials.host, &credentials.account).hash(&mut hasher);
+    Some(
+        dirs::home_dir()?
+            .join(".k9/cache")
+            .join(format!("{}-{:016x}-{}.json", profile, hasher.finish(), kind)),
+    )
+}
+
+fn read_cache(path: &Path) -> Option<Vec<Candidate>> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    if SystemTime::now().duration_since(modified).ok()? > CACHE_TTL {
+        return None;
+    }
+    serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+fn write_cache(path: &Path, candidates: &[Candidate]) {
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    if let Ok(json) = serde_json::to_string(candidates) {
+        let _ = fs::write(path, json);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_flags_from_the_command_line_being_completed() {
+        let words: Vec<String> = [
+            "k9",
+            "--profile",
+            "work",
+            "projects",
+            "logs",
+            "--host=https://canine.internal",
+            "--project",
+        ]
+        .iter()
+        .map(|word| word.to_string())
+        .collect();
+
+        assert_eq!(flag_value(&words, "profile").as_deref(), Some("work"));
+        assert_eq!(flag_value(&words, "host").as_deref(), Some("https://canine.internal"));
+        assert_eq!(flag_value(&words, "project"), None);
+        assert_eq!(flag_value(&words, "token"), None);
+    }
+}
@@ -1,18 +1,166 @@
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
 use colored::Colorize;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::secrets::{self, Secrets};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Profile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    /// Plaintext token, only written when no secret store is usable.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// Where the token is kept, e.g. `keyring:default`.
+    #[serde(
+        rename = "token-ref",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub token_ref: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account: Option<String>,
+}
+
+impl Profile {
+    /// Fills the settings missing here from `fallback`.
+    pub fn or(self, fallback: Profile) -> Profile {
+        Profile {
+            host: self.host.or(fallback.host),
+            token: self.token.or(fallback.token),
+            token_ref: self.token_ref.or(fallback.token_ref),
+            account: self.account.or(fallback.account),
+        }
+    }
+}
+
+/// On-disk layout of `~/.k9/canine.yaml`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ConfigFile {
+    #[serde(
+        rename = "current-profile",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub current_profile: Option<String>,
+
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+
+    /// Single-profile configs written by older versions kept these at the top level.
+    #[serde(flatten, skip_serializing)]
+    legacy: Profile,
+}
+
+impl ConfigFile {
+    pub fn load() -> Self {
+        CanineConfig::gate_directory(&CanineConfig::config_path());
+
+        fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .read(true)
+            .open(CanineConfig::config_path())
+            .unwrap_or_else(|_| panic!("failed to open {}",
+                CanineConfig::config_path().to_str().unwrap()));
+
+        let contents = std::fs::read_to_string(CanineConfig::config_path()).unwrap_or_else(|_| panic!("failed to read {}",
+            CanineConfig::config_path().to_str().unwrap()));
+
+        let mut file: ConfigFile = if contents.trim().is_empty() {
+            ConfigFile::default()
+        } else {
+            serde_yaml::from_str(&contents).unwrap_or_else(|_| panic!("failed to parse {}",
+                CanineConfig::config_path().to_str().unwrap()))
+        };
+
+        if file.migrate_legacy() {
+            // Best effort: the migrated layout is rewritten on the next save otherwise.
+            let _ = file.write();
+        }
+        file
+    }
+
+    /// Moves tokens stored in plaintext into the secret store. Only done when
+    /// saving, so reading the config (as every <TAB> does) never touches the store.
+    fn migrate_plaintext_tokens(&mut self, secrets: &Secrets) -> bool {
+        let mut migrated = false;
+        for (name, profile) in self.profiles.iter_mut() {
+            if profile.token_ref.is_some() {
+                continue;
+            }
+            if let Some(token) = &profile.token
+                && let Ok(reference) = secrets.store(name, token)
+            {
+                profile.token = None;
+                profile.token_ref = Some(reference);
+                migrated = true;
+            }
+        }
+        migrated
+    }
+
+    /// Removes a profile along with its stored token.
+    pub fn remove_profile(&mut self, name: &str, secrets: &Secrets) -> Option<Profile> {
+        let profile = self.profiles.remove(name)?;
+        if let Some(reference) = &profile.token_ref {
+            let _ = secrets.remove(reference);
+        }
+        if self.current_profile.as_deref() == Some(name) {
+            self.current_profile = None;
+        }
+        Some(profile)
+    }
+
+    /// Moves a pre-profiles config into a profile named `default`.
+    fn migrate_legacy(&mut self) -> bool {
+        let legacy = std::mem::take(&mut self.legacy);
+        if legacy.host.is_none() && legacy.token.is_none() && legacy.account.is_none() {
+            return false;
+        }
+        self.profiles
+            .entry(CanineConfig::DEFAULT_PROFILE.to_string())
+            .or_insert(legacy);
+        self.current_profile
+            .get_or_insert_with(|| CanineConfig::DEFAULT_PROFILE.to_string());
+        true
+    }
+
+    pub fn current_profile(&self) -> &str {
+        self.current_profile
+            .as_deref()
+            .unwrap_or(CanineConfig::DEFAULT_PROFILE)
+    }
+
+    /// Writes the config, first moving any plaintext tokens into the secret store.
+    pub fn save(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.migrate_plaintext_tokens(&CanineConfig::secrets());
+        self.write()
+    }
+
+    fn write(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let yaml = serde_yaml::to_string(&self)?;
+        Ok(secrets::write_private(&CanineConfig::config_path(), yaml.as_bytes())?)
+    }
+}
+
+/// The active profile, resolved from `--profile`, `CANINE_PROFILE` or the
+/// `current-profile` stored in the config file.
+#[derive(Debug)]
 pub struct CanineConfig {
+    pub profile: String,
     pub host: Option<String>,
     pub token: Option<String>,
     pub account: Option<String>,
 }
 
 impl CanineConfig {
-    pub const DEFAULT_HOST: &'static str = "https://canine.sh";
+    pub const DEFAULT_HOST: &'static str = crate::client::DEFAULT_HOST;
+    pub const DEFAULT_PROFILE: &'static str = "default";
 
     pub fn credential_path() -> PathBuf {
         dirs::home_dir()
@@ -20,53 +168,113 @@ impl CanineConfig {
             .join(".k9/kubeconfig.yaml")
     }
 
+    /// Per-cluster kubeconfig written by `clusters download-kubeconfig --per-cluster`.
+    pub fn cluster_credential_path(name: &str) -> PathBuf {
+        dirs::home_dir()
+            .expect("Could not determine home directory")
+            .join(".k9/clusters")
+            .join(format!("{}.yaml", name))
+    }
+
     pub fn config_path() -> PathBuf {
         dirs::home_dir()
             .expect("Could not determine home directory")
             .join(".k9/canine.yaml")
     }
 
+    pub fn secrets() -> Secrets {
+        Secrets::system(Self::config_path().parent().unwrap_or(Path::new(".")))
+    }
+
     fn gate_directory(path: &Path) {
         let dir = path.parent().unwrap_or(Path::new("."));
         fs::create_dir_all(dir).expect("Failed to create parent directory");
     }
 
-    pub fn load() -> Self {
-        Self::gate_directory(&Self::config_path());
-
-        fs::OpenOptions::new()
-            .write(true)
-            .create(true)
-            .read(true)
-            .open(Self::config_path())
-            .unwrap_or_else(|_| panic!("failed to open {}",
-                Self::config_path().to_str().unwrap()));
+    pub fn load(profile: Option<&str>) -> Self {
+        let file = ConfigFile::load();
+        let name = profile.unwrap_or(file.current_profile()).to_string();
+        let stored = file.profiles.get(&name).cloned().unwrap_or_default();
 
-        let contents = std::fs::read_to_string(Self::config_path()).unwrap_or_else(|_| panic!("failed to read {}",
-            Self::config_path().to_str().unwrap()));
+        let token = match &stored.token_ref {
+            Some(reference) => Self::secrets().lookup(reference).unwrap_or_else(|e| {
+                eprintln!("{} Could not read token for profile {}: {}", "!".yellow(), name, e);
+                None
+            }),
+            None => stored.token,
+        };
 
-        let config: CanineConfig = serde_yaml::from_str(&contents).unwrap_or_else(|_| panic!("failed to parse {}",
-            Self::config_path().to_str().unwrap()));
+        CanineConfig {
+            profile: name,
+            host: stored.host,
+            token,
+            account: stored.account,
+        }
+    }
 
-        config
+    /// Layers `overrides` on top of the stored profile. Flags take precedence over
+    /// environment variables (clap resolves those), which take precedence over the profile.
+    pub fn resolve(&self, overrides: &Profile) -> Profile {
+        Profile {
+            host: overrides.host.clone().or_else(|| self.host.clone()),
+            token: overrides.token.clone().or_else(|| self.token.clone()),
+            token_ref: None,
+            account: overrides.account.clone().or_else(|| self.account.clone()),
+        }
     }
 
+    /// Writes this profile back to the config file, leaving other profiles untouched.
     pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let yaml = serde_yaml::to_string(&self)?;
-        Ok(fs::write(Self::config_path(), yaml)?)
+        let mut file = ConfigFile::load();
+        let secrets = Self::secrets();
+        let previous = file.profiles.remove(&self.profile).unwrap_or_default();
+
+        let mut profile = Profile {
+            host: self.host.clone(),
+            token: None,
+            token_ref: previous.token_ref.clone(),
+            account: self.account.clone(),
+        };
+        if let Some(token) = &self.token {
+            match secrets.store(&self.profile, token) {
+                Ok(reference) => {
+                    if let Some(old) = previous.token_ref.filter(|old| *old != reference) {
+                        let _ = secrets.remove(&old);
+                    }
+                    profile.token_ref = Some(reference);
+                }
+                Err(e) => {
+                    eprintln!(
+                        "{} No secret store available ({}), saving token in plaintext",
+                        "!".yellow(),
+                        e
+                    );
+                    profile.token = Some(token.clone());
+                }
+            }
+        }
+
+        file.profiles.insert(self.profile.clone(), profile);
+        file.current_profile.get_or_insert_with(|| self.profile.clone());
+        file.save()
     }
 
-    pub fn clear() {
-        Self::gate_directory(&Self::config_path());
-        fs::OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .open(Self::config_path())
-            .unwrap();
+    /// Forgets the token of this profile, removing it from the secret store.
+    pub fn clear(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let mut file = ConfigFile::load();
+        if let Some(profile) = file.profiles.get_mut(&self.profile) {
+            if let Some(reference) = profile.token_ref.take() {
+                Self::secrets().remove(&reference)?;
+            }
+            profile.token = None;
+            file.save()?;
+        }
+        Ok(())
     }
 
     pub fn change_account(&self, account: &str) -> Result<(), Box<dyn std::error::Error>> {
         let config = CanineConfig {
+            profile: self.profile.clone(),
             host: self.host.clone(),
             token: self.token.clone(),
             account: Some(account.to_string()),
@@ -75,9 +283,8 @@ impl CanineConfig {
     }
 
     pub fn save_kubeconfig(&self, yaml: String) -> Result<(), Box<dyn std::error::Error>> {
-        Self::gate_directory(&Self::config_path());
-        fs::write(Self::credential_path(), yaml)?;
-        println!(
+        secrets::write_private(&Self::credential_path(), yaml.as_bytes())?;
+        eprintln!(
             "{} Kubeconfig saved to {}",
             "✓".green(),
             Self::credential_path().to_str().unwrap().cyan()
@@ -85,3 +292,82 @@ impl CanineConfig {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_single_profile_config() {
+        let mut file: ConfigFile = serde_yaml::from_str(
+            "host: https://canine.example.com\ntoken: abc\naccount: acme\n",
+        )
+        .unwrap();
+
+        assert!(file.migrate_legacy());
+        assert_eq!(file.current_profile(), "default");
+        let profile = &file.profiles["default"];
+        assert_eq!(profile.host.as_deref(), Some("https://canine.example.com"));
+        assert_eq!(profile.token.as_deref(), Some("abc"));
+        assert_eq!(profile.account.as_deref(), Some("acme"));
+
+        let yaml = serde_yaml::to_string(&file).unwrap();
+        let reparsed: ConfigFile = serde_yaml::from_str(&yaml).unwrap();
+        assert!(reparsed.legacy.token.is_none());
+        assert_eq!(reparsed.profiles["default"].token.as_deref(), Some("abc"));
+    }
+
+    #[test]
+    fn overrides_take_precedence_over_profile() {
+        let config = CanineConfig {
+            profile: "default".to_string(),
+            host: Some("https://canine.sh".to_string()),
+            token: Some("stored".to_string()),
+            account: Some("acme".to_string()),
+        };
+
+        let resolved = config.resolve(&Profile {
+            token: Some("from-env".to_string()),
+            ..Default::default()
+        });
+
+        assert_eq!(resolved.host.as_deref(), Some("https://canine.sh"));
+        assert_eq!(resolved.token.as_deref(), Some("from-env"));
+        assert_eq!(resolved.account.as_deref(), Some("acme"));
+    }
+
+    #[test]
+    fn moves_plaintext_tokens_into_secret_store() {
+        use crate::secrets::mock::MemoryStore;
+
+        let mut file: ConfigFile = serde_yaml::from_str(
+            "profiles:\n  default:\n    token: abc\n  other:\n    token-ref: keyring:other\n",
+        )
+        .unwrap();
+        let secrets = Secrets::new(Box::<MemoryStore>::default(), Box::<MemoryStore>::default());
+
+        assert!(file.migrate_plaintext_tokens(&secrets));
+        let profile = &file.profiles["default"];
+        assert!(profile.token.is_none());
+        assert_eq!(profile.token_ref.as_deref(), Some("keyring:default"));
+        assert_eq!(secrets.lookup("keyring:default").unwrap().as_deref(), Some("abc"));
+
+        let yaml = serde_yaml::to_string(&file).unwrap();
+        assert!(!yaml.contains("abc"));
+
+        assert!(file.remove_profile("default", &secrets).is_some());
+        assert_eq!(secrets.lookup("keyring:default").unwrap(), None);
+    }
+
+    #[test]
+    fn leaves_profile_config_alone() {
+        let mut file: ConfigFile = serde_yaml::from_str(
+            "current-profile: work\nprofiles:\n  work:\n    host: https://canine.sh\n    token: abc\n",
+        )
+        .unwrap();
+
+        assert!(!file.migrate_legacy());
+        assert_eq!(file.current_profile(), "work");
+        assert_eq!(file.profiles.len(), 1);
+    }
+}
@@ -0,0 +1,605 @@
+//! Runs commands in pods over the Kubernetes exec subprotocol, so `projects run`
+//! needs neither kubectl nor credentials on disk.
+//!
+//! Every WebSocket frame starts with a channel byte: 0 stdin, 1 stdout, 2 stderr,
+//! 3 the final status and 4 terminal resizes. `v5.channel.k8s.io` adds frame 255
+//! to close a channel, which is how the remote command sees the end of piped input.
+
+use std::io::{self, IsTerminal, Read, Write};
+use std::sync::Arc;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use futures_util::{SinkExt, StreamExt};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{CryptoProvider, WebPkiSupportedAlgorithms};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use serde::Deserialize;
+use thiserror::Error;
+use tokio::sync::mpsc;
+use tokio_tungstenite::Connector;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::{self, Message};
+
+use crate::kubeconfig::{Cluster, Kubeconfig, User};
+
+const PROTOCOLS: &str = "v5.channel.k8s.io, v4.channel.k8s.io";
+const STDIN: u8 = 0;
+const STDOUT: u8 = 1;
+const STDERR: u8 = 2;
+const STATUS: u8 = 3;
+const RESIZE: u8 = 4;
+const CLOSE: u8 = 255;
+
+#[derive(Debug, Error)]
+pub enum ExecError {
+    #[error("kubeconfig does not define {0}")]
+    MissingEntry(String),
+    #[error("{0} credentials are not supported, use a kubeconfig with a token or client certificate")]
+    UnsupportedAuth(&'static str),
+    #[error("invalid certificate or key: {0}")]
+    Certificate(String),
+    #[error("cluster rejected the exec request ({status}): {message}")]
+    Rejected { status: u16, message: String },
+    #[error("command failed: {0}")]
+    Remote(String),
+    #[error(
+        "the cluster cannot be told when input ends (it does not support v5.channel.k8s.io, added in \
+         Kubernetes 1.30), so a command reading piped input would never finish. Run it from a terminal, \
+         or pass --interactive=false if it does not read stdin"
+    )]
+    StdinNotClosable,
+    #[error(transparent)]
+    Tls(#[from] rustls::Error),
+    #[error(transparent)]
+    Url(#[from] url::ParseError),
+    #[error(transparent)]
+    WebSocket(tungstenite::Error),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+impl From<tungstenite::Error> for ExecError {
+    fn from(error: tungstenite::Error) -> Self {
+        match error {
+            tungstenite::Error::Http(response) => ExecError::Rejected {
+                status: response.status().as_u16(),
+                message: response
+                    .body()
+                    .as_deref()
+                    .map(|body| String::from_utf8_lossy(body).trim().to_string())
+                    .unwrap_or_default(),
+            },
+            other => ExecError::WebSocket(other),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExecOptions {
+    pub command: Vec<String>,
+    pub container: Option<String>,
+    pub stdin: bool,
+    pub tty: bool,
+}
+
+/// What the local side sends to the remote command.
+#[derive(Debug)]
+pub enum Input {
+    Data(Vec<u8>),
+    Eof,
+    Resize { width: u16, height: u16 },
+}
+
+/// Runs `options.command` in the pod attached to this process' stdin, stdout and
+/// stderr, and returns the remote exit code.
+pub async fn exec_attached(
+    kubeconfig: &Kubeconfig,
+    namespace: &str,
+    pod: &str,
+    options: &ExecOptions,
+) -> Result<i32, ExecError> {
+    let (tx, rx) = mpsc::channel(16);
+    let _raw_mode = if options.tty { Some(RawMode::enable()?) } else { None };
+
+    if options.stdin {
+        let tx = tx.clone();
+        // tokio's stdin would keep the runtime alive at exit while blocked on a read.
+        std::thread::spawn(move || {
+            let mut stdin = io::stdin();
+            let mut buf = [0u8; 8192];
+            loop {
+                match stdin.read(&mut buf) {
+                    Ok(0) | Err(_) => {
+                        let _ = tx.blocking_send(Input::Eof);
+                        return;
+                    }
+                    Ok(n) => {
+                        if tx.blocking_send(Input::Data(buf[..n].to_vec())).is_err() {
+                            return;
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    let resizes = options.tty.then(|| {
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            if let Ok((width, height)) = crossterm::terminal::size() {
+                let _ = tx.send(Input::Resize { width, height }).await;
+            }
+            #[cfg(unix)]
+            {
+                use tokio::signal::unix::{SignalKind, signal};
+                let Ok(mut changes) = signal(SignalKind::window_change()) else {
+                    return;
+                };
+                while changes.recv().await.is_some() {
+                    if let Ok((width, height)) = crossterm::terminal::size() {
+                        let _ = tx.send(Input::Resize { width, height }).await;
+                    }
+                }
+            }
+        })
+    });
+    drop(tx);
+
+    let result = exec(
+        kubeconfig,
+        namespace,
+        pod,
+        options,
+        rx,
+        &mut io::stdout(),
+        &mut io::stderr(),
+    )
+    .await;
+    if let Some(resizes) = resizes {
+        resizes.abort();
+    }
+    result
+}
+
+/// Runs a command in a pod, feeding it `input` and copying its output to
+/// `stdout` and `stderr`. Returns the remote exit code.
+pub async fn exec(
+    kubeconfig: &Kubeconfig,
+    namespace: &str,
+    pod: &str,
+    options: &ExecOptions,
+    mut input: mpsc::Receiver<Input>,
+    stdout: &mut dyn Write,
+    stderr: &mut dyn Write,
+) -> Result<i32, ExecError> {
+    let (cluster, user) = current_context(kubeconfig)?;
+    let url = exec_url(&cluster.server, namespace, pod, options)?;
+
+    let mut request = url.as_str().into_client_request()?;
+    let headers = request.headers_mut();
+    headers.insert("Sec-WebSocket-Protocol", HeaderValue::from_static(PROTOCOLS));
+    if let Some(authorization) = authorization(user)? {
+        headers.insert(
+            "Authorization",
+            HeaderValue::from_str(&authorization).map_err(|_| ExecError::UnsupportedAuth("malformed token"))?,
+        );
+    }
+
+    let connector = match url.scheme() {
+        "wss" => Connector::Rustls(Arc::new(tls_config(cluster, user)?)),
+        _ => Connector::Plain,
+    };
+    let (socket, response) =
+        tokio_tungstenite::connect_async_tls_with_config(request, None, true, Some(connector)).await?;
+    let can_close = response
+        .headers()
+        .get("Sec-WebSocket-Protocol")
+        .is_some_and(|protocol| protocol.as_bytes().starts_with(b"v5."));
+    // Without a TTY the end of input can only be sent as a v5 close frame; with
+    // one, Ctrl-D reaches the remote terminal like any other key.
+    if options.stdin && !options.tty && !can_close {
+        return Err(ExecError::StdinNotClosable);
+    }
+
+    let (mut sink, mut stream) = socket.split();
+    let mut input_open = true;
+    let mut exit_code = 0;
+
+    loop {
+        tokio::select! {
+            message = stream.next() => {
+                let data = match message {
+                    Some(Ok(Message::Binary(data))) => data,
+                    Some(Ok(Message::Close(_))) | None => break,
+                    Some(Ok(_)) => continue,
+                    Some(Err(tungstenite::Error::ConnectionClosed)) => break,
+                    Some(Err(e)) => return Err(e.into()),
+                };
+                let Some((&channel, payload)) = data.split_first() else {
+                    continue;
+                };
+                match channel {
+                    STDOUT => {
+                        stdout.write_all(payload)?;
+                        stdout.flush()?;
+                    }
+                    STDERR => {
+                        stderr.write_all(payload)?;
+                        stderr.flush()?;
+                    }
+                    STATUS => exit_code = parse_status(payload)?,
+                    _ => {}
+                }
+            }
+            next = input.recv(), if input_open => {
+                let frame = match next {
+                    Some(Input::Data(data)) => [&[STDIN][..], &data].concat(),
+                    Some(Input::Resize { width, height }) => {
+                        let size = serde_json::json!({ "Width": width, "Height": height });
+                        [&[RESIZE][..], size.to_string().as_bytes()].concat()
+                    }
+                    Some(Input::Eof) | None => {
+                        input_open = false;
+                        if !can_close || !options.stdin {
+                            continue;
+                        }
+                        vec![CLOSE, STDIN]
+                    }
+                };
+                sink.send(Message::binary(frame)).await?;
+            }
+        }
+    }
+
+    Ok(exit_code)
+}
+
+#[derive(Debug, Deserialize)]
+struct Status {
+    status: String,
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    reason: String,
+    details: Option<StatusDetails>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StatusDetails {
+    #[serde(default)]
+    causes: Vec<StatusCause>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StatusCause {
+    #[serde(default)]
+    reason: String,
+    #[serde(default)]
+    message: String,
+}
+
+/// Turns the `metav1.Status` sent on the status channel into an exit code.
+fn parse_status(payload: &[u8]) -> Result<i32, ExecError> {
+    let status: Status =
+        serde_json::from_slice(payload).map_err(|_| ExecError::Remote(String::from_utf8_lossy(payload).to_string()))?;
+    if status.status == "Success" {
+        return Ok(0);
+    }
+    if status.reason == "NonZeroExitCode"
+        && let Some(code) = status
+            .details
+            .iter()
+            .flat_map(|details| &details.causes)
+            .find(|cause| cause.reason == "ExitCode")
+            .and_then(|cause| cause.message.parse().ok())
+    {
+        return Ok(code);
+    }
+    Err(ExecError::Remote(status.message))
+}
+
+fn current_context(kubeconfig: &Kubeconfig) -> Result<(&Cluster, &User), ExecError> {
+    let context = match &kubeconfig.current_context {
+        Some(name) => kubeconfig.contexts.iter().find(|c| &c.name == name),
+        None => kubeconfig.contexts.first(),
+    }
+    .ok_or_else(|| ExecError::MissingEntry("a current context".to_string()))?;
+
+    let cluster = kubeconfig
+        .clusters
+        .iter()
+        .find(|c| c.name == context.context.cluster)
+        .ok_or_else(|| ExecError::MissingEntry(format!("cluster {}", context.context.cluster)))?;
+    let user = kubeconfig
+        .users
+        .iter()
+        .find(|u| u.name == context.context.user)
+        .ok_or_else(|| ExecError::MissingEntry(format!("user {}", context.context.user)))?;
+    Ok((&cluster.cluster, &user.user))
+}
+
+fn exec_url(server: &str, namespace: &str, pod: &str, options: &ExecOptions) -> Result<url::Url, ExecError> {
+    let mut url = url::Url::parse(server)?;
+    let path = format!(
+        "{}/api/v1/namespaces/{}/pods/{}/exec",
+        url.path().trim_end_matches('/'),
+        namespace,
+        pod
+    );
+    url.set_path(&path);
+    let scheme = if url.scheme() == "http" { "ws" } else { "wss" };
+    let _ = url.set_scheme(scheme);
+
+    {
+        let mut query = url.query_pairs_mut();
+        query.clear();
+        for arg in &options.command {
+            query.append_pair("command", arg);
+        }
+        if let Some(container) = &options.container {
+            query.append_pair("container", container);
+        }
+        query.append_pair("stdin", &options.stdin.to_string());
+        query.append_pair("stdout", "true");
+        // With a TTY the remote merges stderr into stdout.
+        query.append_pair("stderr", &(!options.tty).to_string());
+        query.append_pair("tty", &options.tty.to_string());
+    }
+    Ok(url)
+}
+
+fn authorization(user: &User) -> Result<Option<String>, ExecError> {
+    if user.exec.is_some() {
+        return Err(ExecError::UnsupportedAuth("exec plugin"));
+    }
+    if user.auth_provider.is_some() {
+        return Err(ExecError::UnsupportedAuth("auth provider"));
+    }
+    if let Some(token) = &user.token {
+        return Ok(Some(format!("Bearer {}", token)));
+    }
+    if let Some(path) = &user.token_file {
+        return Ok(Some(format!("Bearer {}", std::fs::read_to_string(path)?.trim())));
+    }
+    if let (Some(username), Some(password)) = (&user.username, &user.password) {
+        return Ok(Some(format!("Basic {}", BASE64.encode(format!("{}:{}", username, password)))));
+    }
+    Ok(None)
+}
+
+/// Reads an inline `*-data` field (base64 PEM) or else the file it points to.
+fn pem(data: &Option<String>, path: &Option<String>) -> Result<Option<Vec<u8>>, ExecError> {
+    if let Some(data) = data {
+        return BASE64
+            .decode(data.trim())
+            .map(Some)
+            .map_err(|e| ExecError::Certificate(e.to_string()));
+    }
+    match path {
+        Some(path) => Ok(Some(std::fs::read(path)?)),
+        None => Ok(None),
+    }
+}
+
+fn tls_config(cluster: &Cluster, user: &User) -> Result<ClientConfig, ExecError> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(provider.clone()).with_safe_default_protocol_versions()?;
+
+    let builder = if cluster.insecure_skip_tls_verify == Some(true) {
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(NoVerification(provider)))
+    } else {
+        let mut roots = RootCertStore::empty();
+        match pem(&cluster.certificate_authority_data, &cluster.certificate_authority)? {
+            Some(ca) => {
+                for cert in CertificateDer::pem_slice_iter(&ca) {
+                    roots.add(cert.map_err(|e| ExecError::Certificate(e.to_string()))?)?;
+                }
+            }
+            None => roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned()),
+        }
+        builder.with_root_certificates(roots)
+    };
+
+    let cert = pem(&user.client_certificate_data, &user.client_certificate)?;
+    let key = pem(&user.client_key_data, &user.client_key)?;
+    match (cert, key) {
+        (Some(cert), Some(key)) => {
+            let certs = CertificateDer::pem_slice_iter(&cert)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| ExecError::Certificate(e.to_string()))?;
+            let key = PrivateKeyDer::from_pem_slice(&key).map_err(|e| ExecError::Certificate(e.to_string()))?;
+            Ok(builder.with_client_auth_cert(certs, key)?)
+        }
+        _ => Ok(builder.with_no_client_auth()),
+    }
+}
+
+/// Honours `insecure-skip-tls-verify`: signatures are still checked, the
+/// certificate chain is not.
+#[derive(Debug)]
+struct NoVerification(Arc<CryptoProvider>);
+
+impl NoVerification {
+    fn algorithms(&self) -> &WebPkiSupportedAlgorithms {
+        &self.0.signature_verification_algorithms
+    }
+}
+
+impl ServerCertVerifier for NoVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, self.algorithms())
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, self.algorithms())
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms().supported_schemes()
+    }
+}
+
+/// Puts the local terminal in raw mode so keystrokes such as Ctrl-C reach the
+/// remote TTY, and restores it when dropped.
+struct RawMode;
+
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        if io::stdin().is_terminal() {
+            crossterm::terminal::enable_raw_mode()?;
+        }
+        Ok(RawMode)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = crossterm::terminal::disable_raw_mode();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kubeconfig::parse_kubeconfig_yaml;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+
+    fn kubeconfig(server: &str) -> Kubeconfig {
+        parse_kubeconfig_yaml(&format!(
+            "apiVersion: v1\nkind: Config\ncurrent-context: demo\n\
+             clusters:\n- name: demo\n  cluster:\n    server: {}\n\
+             users:\n- name: admin\n  user:\n    token: secret\n\
+             contexts:\n- name: demo\n  context:\n    cluster: demo\n    user: admin\n",
+            server
+        ))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    #[allow(clippy::result_large_err)] // the handshake callback signature is tungstenite's
+    async fn streams_input_and_output_and_returns_exit_code() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server = format!("http://{}/k8s", listener.local_addr().unwrap());
+
+        let fake = tokio::spawn(async move {
+            let (tcp, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_hdr_async(tcp, |request: &Request, mut response: Response| {
+                assert_eq!(
+                    request.uri().to_string(),
+                    "/k8s/api/v1/namespaces/api/pods/api-run-1/exec?command=psql&command=-q\
+                     &stdin=true&stdout=true&stderr=true&tty=false"
+                );
+                assert_eq!(request.headers()["Authorization"], "Bearer secret");
+                response
+                    .headers_mut()
+                    .insert("Sec-WebSocket-Protocol", HeaderValue::from_static("v5.channel.k8s.io"));
+                Ok(response)
+            })
+            .await
+            .unwrap();
+
+            let mut received = Vec::new();
+            while let Some(Ok(Message::Binary(data))) = socket.next().await {
+                if data[..] == [CLOSE, STDIN] {
+                    break;
+                }
+                assert_eq!(data[0], STDIN);
+                received.extend_from_slice(&data[1..]);
+            }
+
+            socket.send(Message::binary([&[STDOUT][..], &received].concat())).await.unwrap();
+            socket.send(Message::binary(b"\x02warning\n".to_vec())).await.unwrap();
+            let status = r#"{"status":"Failure","message":"command terminated with non-zero exit code","reason":"NonZeroExitCode","details":{"causes":[{"reason":"ExitCode","message":"3"}]}}"#;
+            socket.send(Message::binary([&[STATUS][..], status.as_bytes()].concat())).await.unwrap();
+            socket.close(None).await.unwrap();
+        });
+
+        let (tx, rx) = mpsc::channel(4);
+        tx.send(Input::Data(b"select 1;".to_vec())).await.unwrap();
+        tx.send(Input::Eof).await.unwrap();
+
+        let options = ExecOptions {
+            command: vec!["psql".to_string(), "-q".to_string()],
+            stdin: true,
+            ..Default::default()
+        };
+        let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+        let code = exec(&kubeconfig(&server), "api", "api-run-1", &options, rx, &mut stdout, &mut stderr)
+            .await
+            .unwrap();
+        fake.await.unwrap();
+
+        assert_eq!(code, 3);
+        assert_eq!(stdout, b"select 1;");
+        assert_eq!(stderr, b"warning\n");
+    }
+
+    #[tokio::test]
+    #[allow(clippy::result_large_err)] // the handshake callback signature is tungstenite's
+    async fn refuses_piped_input_without_v5() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server = format!("http://{}", listener.local_addr().unwrap());
+
+        let fake = tokio::spawn(async move {
+            let (tcp, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_hdr_async(tcp, |_: &Request, mut response: Response| {
+                response
+                    .headers_mut()
+                    .insert("Sec-WebSocket-Protocol", HeaderValue::from_static("v4.channel.k8s.io"));
+                Ok(response)
+            })
+            .await
+            .unwrap();
+            while let Some(Ok(_)) = socket.next().await {}
+        });
+
+        let (_tx, rx) = mpsc::channel(1);
+        let options = ExecOptions {
+            command: vec!["psql".to_string()],
+            stdin: true,
+            ..Default::default()
+        };
+        let err = exec(&kubeconfig(&server), "api", "api-run-1", &options, rx, &mut Vec::new(), &mut Vec::new())
+            .await
+            .unwrap_err();
+        fake.await.unwrap();
+
+        assert!(matches!(err, ExecError::StdinNotClosable));
+    }
+
+    #[test]
+    fn parses_exec_status() {
+        assert_eq!(parse_status(br#"{"status":"Success"}"#).unwrap(), 0);
+        let err = parse_status(br#"{"status":"Failure","message":"container not found","reason":"InternalError"}"#)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "command failed: container not found");
+    }
+}
@@ -1,32 +1,38 @@
-//! Kubeconfig schema + (YAML <-> struct <-> JSON) using serde.
-//!
-//! Add deps:
-//! serde = { version = "1", features = ["derive"] }
-//! serde_yaml = "0.9"
-//! serde_json = "1"
+//! Kubeconfig schema + (YAML <-> struct <-> JSON) using serde, and merging
+//! downloaded credentials into an existing kubeconfig.
 
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
 
+/// A kubeconfig file, as read by kubectl and client-go.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct Kubeconfig {
+    /// Always `v1`.
     #[serde(rename = "apiVersion")]
     pub api_version: String,
 
+    /// Always `Config`.
     pub kind: String,
 
+    /// Settings for kubectl itself.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub preferences: Option<Preferences>,
 
+    /// API servers and how to trust them.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub clusters: Vec<NamedCluster>,
 
+    /// Credentials.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub users: Vec<NamedUser>,
 
+    /// Pairs of a cluster and a user, with an optional namespace.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub contexts: Vec<NamedContext>,
 
+    /// The context used when none is given.
     #[serde(
         rename = "current-context",
         default,
@@ -37,25 +43,39 @@ pub struct Kubeconfig {
     /// Kubeconfig supports arbitrary extensions.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub extensions: Vec<NamedExtension>,
+
+    /// Keys this schema does not know, kept so merging into a file does not drop them.
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
 }
 
+/// kubectl preferences, kept as they are.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[non_exhaustive]
 pub struct Preferences {
     /// Often an empty object in kubeconfigs.
     #[serde(flatten)]
     pub extra: BTreeMap<String, serde_json::Value>,
 }
 
+/// An entry of `clusters`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct NamedCluster {
+    /// Name contexts refer to the cluster by.
     pub name: String,
+    /// How to reach the cluster.
     pub cluster: Cluster,
 }
 
+/// How to reach and trust an API server.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct Cluster {
+    /// URL of the API server.
     pub server: String,
 
+    /// Path to a PEM file with the CA that signed the server's certificate.
     #[serde(
         rename = "certificate-authority",
         default,
@@ -63,6 +83,7 @@ pub struct Cluster {
     )]
     pub certificate_authority: Option<String>,
 
+    /// The same CA, base64 encoded inline.
     #[serde(
         rename = "certificate-authority-data",
         default,
@@ -70,6 +91,7 @@ pub struct Cluster {
     )]
     pub certificate_authority_data: Option<String>,
 
+    /// Skips verifying the server's certificate. Cannot be combined with a CA.
     #[serde(
         rename = "insecure-skip-tls-verify",
         default,
@@ -77,9 +99,11 @@ pub struct Cluster {
     )]
     pub insecure_skip_tls_verify: Option<bool>,
 
+    /// Proxy for requests to this cluster.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub proxy_url: Option<String>,
 
+    /// Tool specific additions.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub extensions: Vec<NamedExtension>,
 
@@ -88,39 +112,58 @@ pub struct Cluster {
     pub extra: BTreeMap<String, serde_json::Value>,
 }
 
+/// An entry of `contexts`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct NamedContext {
+    /// Name passed to `kubectl --context`.
     pub name: String,
+    /// What the context points at.
     pub context: Context,
 }
 
+/// A cluster and user to use together.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct Context {
+    /// Name of an entry in `clusters`.
     pub cluster: String,
+    /// Name of an entry in `users`.
     pub user: String,
 
+    /// Namespace used when a command does not name one.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub namespace: Option<String>,
 
+    /// Tool specific additions.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub extensions: Vec<NamedExtension>,
 
+    /// Keys this schema does not know, kept as they are.
     #[serde(flatten)]
     pub extra: BTreeMap<String, serde_json::Value>,
 }
 
+/// An entry of `users`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct NamedUser {
+    /// Name contexts refer to the user by.
     pub name: String,
+    /// The credentials.
     pub user: User,
 }
 
+/// Credentials for an API server. Usually only one way to authenticate is set.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[non_exhaustive]
 pub struct User {
     // Common static auth fields
+    /// Bearer token.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub token: Option<String>,
 
+    /// Path to a file holding the bearer token.
     #[serde(
         rename = "token-file",
         default,
@@ -128,12 +171,15 @@ pub struct User {
     )]
     pub token_file: Option<String>,
 
+    /// Basic auth user name.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub username: Option<String>,
 
+    /// Basic auth password.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub password: Option<String>,
 
+    /// Path to a PEM client certificate.
     #[serde(
         rename = "client-certificate",
         default,
@@ -141,6 +187,7 @@ pub struct User {
     )]
     pub client_certificate: Option<String>,
 
+    /// The client certificate, base64 encoded inline.
     #[serde(
         rename = "client-certificate-data",
         default,
@@ -148,6 +195,7 @@ pub struct User {
     )]
     pub client_certificate_data: Option<String>,
 
+    /// Path to the PEM key of the client certificate.
     #[serde(
         rename = "client-key",
         default,
@@ -155,6 +203,7 @@ pub struct User {
     )]
     pub client_key: Option<String>,
 
+    /// The client key, base64 encoded inline.
     #[serde(
         rename = "client-key-data",
         default,
@@ -163,9 +212,11 @@ pub struct User {
     pub client_key_data: Option<String>,
 
     // Dynamic auth mechanisms
+    /// A command that prints credentials.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub exec: Option<ExecConfig>,
 
+    /// A legacy auth provider plugin.
     #[serde(
         rename = "auth-provider",
         default,
@@ -173,9 +224,11 @@ pub struct User {
     )]
     pub auth_provider: Option<AuthProvider>,
 
+    /// User to act as.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub impersonate: Option<String>,
 
+    /// Groups to act as.
     #[serde(
         rename = "impersonate-groups",
         default,
@@ -183,6 +236,7 @@ pub struct User {
     )]
     pub impersonate_groups: Vec<String>,
 
+    /// Extra fields of the impersonated user.
     #[serde(
         rename = "impersonate-user-extra",
         default,
@@ -190,6 +244,7 @@ pub struct User {
     )]
     pub impersonate_user_extra: BTreeMap<String, Vec<String>>,
 
+    /// Tool specific additions.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub extensions: Vec<NamedExtension>,
 
@@ -198,20 +253,26 @@ pub struct User {
     pub extra: BTreeMap<String, serde_json::Value>,
 }
 
+/// A credential plugin, such as `aws eks get-token`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct ExecConfig {
     /// K8s client auth exec API version (often: client.authentication.k8s.io/v1beta1 or /v1)
     #[serde(rename = "apiVersion")]
     pub api_version: String,
 
+    /// Program to run.
     pub command: String,
 
+    /// Its arguments.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub args: Vec<String>,
 
+    /// Extra environment variables for it.
     #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
     pub env: BTreeMap<String, String>,
 
+    /// Whether the plugin needs stdin: `Never`, `IfAvailable` or `Always`.
     #[serde(
         rename = "interactiveMode",
         default,
@@ -219,6 +280,7 @@ pub struct ExecConfig {
     )]
     pub interactive_mode: Option<String>,
 
+    /// Passes the cluster details to the plugin.
     #[serde(
         rename = "provideClusterInfo",
         default,
@@ -226,9 +288,11 @@ pub struct ExecConfig {
     )]
     pub provide_cluster_info: Option<bool>,
 
+    /// Shown when the command is missing.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub install_hint: Option<String>,
 
+    /// `installHint`, the spelling client-go uses.
     #[serde(
         rename = "installHint",
         default,
@@ -236,23 +300,32 @@ pub struct ExecConfig {
     )]
     pub install_hint_alt: Option<String>,
 
+    /// Keys this schema does not know, kept as they are.
     #[serde(flatten)]
     pub extra: BTreeMap<String, serde_json::Value>,
 }
 
+/// A legacy auth provider, such as `oidc`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct AuthProvider {
+    /// Name of the provider.
     pub name: String,
 
+    /// Provider specific settings.
     #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
     pub config: BTreeMap<String, String>,
 
+    /// Keys this schema does not know, kept as they are.
     #[serde(flatten)]
     pub extra: BTreeMap<String, serde_json::Value>,
 }
 
+/// Data a tool attached to an entry.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct NamedExtension {
+    /// Name of the extension.
     pub name: String,
 
     /// Extension payload is arbitrary JSON/YAML.
@@ -262,18 +335,261 @@ pub struct NamedExtension {
 
 // -------------------- helpers / example usage --------------------
 
+/// Parses a kubeconfig file.
 pub fn parse_kubeconfig_yaml(yaml: &str) -> Result<Kubeconfig, serde_yaml::Error> {
     serde_yaml::from_str::<Kubeconfig>(yaml)
 }
 
+/// Serializes a kubeconfig as indented JSON.
 pub fn kubeconfig_to_pretty_json(cfg: &Kubeconfig) -> Result<String, serde_json::Error> {
     serde_json::to_string_pretty(cfg)
 }
 
+/// Serializes a kubeconfig as YAML, the format kubectl writes.
 pub fn kubeconfig_to_yaml(cfg: &Kubeconfig) -> Result<String, serde_yaml::Error> {
     serde_yaml::to_string(cfg)
 }
 
+// -------------------- merging --------------------
+
+impl Default for Kubeconfig {
+    fn default() -> Self {
+        Self {
+            api_version: "v1".to_string(),
+            kind: "Config".to_string(),
+            preferences: None,
+            clusters: Vec::new(),
+            users: Vec::new(),
+            contexts: Vec::new(),
+            current_context: None,
+            extensions: Vec::new(),
+            extra: BTreeMap::new(),
+        }
+    }
+}
+
+/// Renames every cluster, user and context to `prefix` (or `prefix-<name>` when
+/// there is more than one of a kind), keeping context references intact.
+pub fn prefix_names(cfg: &mut Kubeconfig, prefix: &str) {
+    fn rename(name: &str, prefix: &str, count: usize) -> String {
+        if count == 1 {
+            prefix.to_string()
+        } else {
+            format!("{}-{}", prefix, name)
+        }
+    }
+
+    let clusters = cfg.clusters.len();
+    let users = cfg.users.len();
+    let contexts = cfg.contexts.len();
+
+    for cluster in &mut cfg.clusters {
+        cluster.name = rename(&cluster.name, prefix, clusters);
+    }
+    for user in &mut cfg.users {
+        user.name = rename(&user.name, prefix, users);
+    }
+    for context in &mut cfg.contexts {
+        context.name = rename(&context.name, prefix, contexts);
+        context.context.cluster = rename(&context.context.cluster, prefix, clusters);
+        context.context.user = rename(&context.context.user, prefix, users);
+    }
+    if let Some(current) = &cfg.current_context {
+        cfg.current_context = Some(rename(current, prefix, contexts));
+    }
+}
+
+/// What changed in the target kubeconfig during a merge.
+#[derive(Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct MergeReport {
+    /// Entries that were new, e.g. `cluster canine-acme-production`.
+    pub added: Vec<String>,
+    /// Entries that overwrote one with the same name.
+    pub replaced: Vec<String>,
+}
+
+/// Merges `source` into `target`. Entries with a name that already exists in
+/// `target` replace the old entry; everything else in `target` is left alone.
+pub fn merge_kubeconfig(target: &mut Kubeconfig, source: Kubeconfig, set_current_context: bool) -> MergeReport {
+    fn upsert<T>(entries: &mut Vec<T>, entry: T, name: impl Fn(&T) -> &str, report: &mut MergeReport, kind: &str) {
+        let label = format!("{} {}", kind, name(&entry));
+        match entries.iter_mut().find(|existing| name(existing) == name(&entry)) {
+            Some(existing) => {
+                *existing = entry;
+                report.replaced.push(label);
+            }
+            None => {
+                entries.push(entry);
+                report.added.push(label);
+            }
+        }
+    }
+
+    let mut report = MergeReport::default();
+    for cluster in source.clusters {
+        upsert(&mut target.clusters, cluster, |c| &c.name, &mut report, "cluster");
+    }
+    for user in source.users {
+        upsert(&mut target.users, user, |u| &u.name, &mut report, "user");
+    }
+    for context in source.contexts {
+        upsert(&mut target.contexts, context, |c| &c.name, &mut report, "context");
+    }
+
+    if set_current_context
+        && let Some(current) = source.current_context
+    {
+        target.current_context = Some(current);
+    }
+    report
+}
+
+/// The kubeconfig kubectl would use: the first entry of `$KUBECONFIG`, or `~/.kube/config`.
+pub fn default_kubeconfig_path() -> PathBuf {
+    std::env::var_os("KUBECONFIG")
+        .and_then(|paths| std::env::split_paths(&paths).next())
+        .filter(|path| !path.as_os_str().is_empty())
+        .unwrap_or_else(|| {
+            dirs::home_dir()
+                .expect("Could not determine home directory")
+                .join(".kube/config")
+        })
+}
+
+// -------------------- linting --------------------
+
+/// How serious a [`Finding`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, strum::Display)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+#[non_exhaustive]
+pub enum Severity {
+    /// kubectl will refuse the entry or pick a different one than intended.
+    Error,
+    /// kubectl accepts the entry, but it is likely a mistake.
+    Warning,
+}
+
+/// A problem found by [`lint`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, tabled::Tabled)]
+#[non_exhaustive]
+pub struct Finding {
+    /// How serious the problem is.
+    pub severity: Severity,
+    /// Where the problem is, e.g. `contexts[production].cluster`.
+    pub location: String,
+    /// What is wrong.
+    pub message: String,
+    /// Stable identifier for scripts, e.g. `dangling-cluster`.
+    pub code: &'static str,
+}
+
+impl Finding {
+    fn error(code: &'static str, location: String, message: String) -> Self {
+        Self { severity: Severity::Error, location, message, code }
+    }
+}
+
+/// Checks the references and files a kubeconfig relies on. Relative paths are
+/// resolved against `base_dir`, the directory of the kubeconfig file, as kubectl does.
+pub fn lint(cfg: &Kubeconfig, base_dir: Option<&Path>) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    let mut duplicates = |kind: &str, names: Vec<&str>| {
+        let mut seen = BTreeSet::new();
+        for name in names {
+            if !seen.insert(name) {
+                findings.push(Finding::error(
+                    "duplicate-name",
+                    format!("{}[{}]", kind, name),
+                    format!("{} is defined more than once, kubectl refuses to load the file", name),
+                ));
+            }
+        }
+    };
+    duplicates("clusters", cfg.clusters.iter().map(|c| c.name.as_str()).collect());
+    duplicates("users", cfg.users.iter().map(|u| u.name.as_str()).collect());
+    duplicates("contexts", cfg.contexts.iter().map(|c| c.name.as_str()).collect());
+
+    for context in &cfg.contexts {
+        if !cfg.clusters.iter().any(|c| c.name == context.context.cluster) {
+            findings.push(Finding::error(
+                "dangling-cluster",
+                format!("contexts[{}].cluster", context.name),
+                format!("cluster {} does not exist", context.context.cluster),
+            ));
+        }
+        if !cfg.users.iter().any(|u| u.name == context.context.user) {
+            findings.push(Finding::error(
+                "dangling-user",
+                format!("contexts[{}].user", context.name),
+                format!("user {} does not exist", context.context.user),
+            ));
+        }
+    }
+
+    if let Some(current) = &cfg.current_context
+        && !cfg.contexts.iter().any(|c| &c.name == current)
+    {
+        findings.push(Finding::error(
+            "dangling-current-context",
+            "current-context".to_string(),
+            format!("context {} does not exist", current),
+        ));
+    }
+
+    let unreadable = |path: &str| {
+        let path = match base_dir {
+            Some(dir) => dir.join(path),
+            None => PathBuf::from(path),
+        };
+        match std::fs::File::open(&path) {
+            Err(e) => Some(format!("cannot read {}: {}", path.display(), e)),
+            Ok(_) if !path.is_file() => Some(format!("{} is not a file", path.display())),
+            Ok(_) => None,
+        }
+    };
+    for cluster in &cfg.clusters {
+        let c = &cluster.cluster;
+        if c.insecure_skip_tls_verify == Some(true)
+            && (c.certificate_authority.is_some() || c.certificate_authority_data.is_some())
+        {
+            findings.push(Finding::error(
+                "insecure-with-ca",
+                format!("clusters[{}]", cluster.name),
+                "insecure-skip-tls-verify is set together with a certificate authority, kubectl rejects this".to_string(),
+            ));
+        }
+        if let Some(message) = c.certificate_authority.as_deref().and_then(unreadable) {
+            findings.push(Finding::error(
+                "unreadable-file",
+                format!("clusters[{}].certificate-authority", cluster.name),
+                message,
+            ));
+        }
+    }
+    for user in &cfg.users {
+        if let Some(message) = user.user.token_file.as_deref().and_then(unreadable) {
+            findings.push(Finding::error(
+                "unreadable-file",
+                format!("users[{}].token-file", user.name),
+                message,
+            ));
+        }
+    }
+
+    if cfg.current_context.is_none() && !cfg.contexts.is_empty() {
+        findings.push(Finding {
+            severity: Severity::Warning,
+            location: "current-context".to_string(),
+            message: "no current-context is set, kubectl needs --context".to_string(),
+            code: "no-current-context",
+        });
+    }
+    findings
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -301,23 +617,204 @@ users:
 
         let cfg = parse_kubeconfig_yaml(yaml).unwrap();
         let json = kubeconfig_to_pretty_json(&cfg).unwrap();
-        assert!(json.contains(r#""current_context""#) || json.contains(r#""current_context":"#));
+        assert!(json.contains(r#""current-context": "demo""#));
         // Also ensure we can dump back to YAML
         let yaml2 = kubeconfig_to_yaml(&cfg).unwrap();
         assert!(yaml2.contains("apiVersion"));
     }
+
+    fn downloaded(server: &str, token: &str) -> Kubeconfig {
+        parse_kubeconfig_yaml(&format!(
+            r#"
+apiVersion: v1
+kind: Config
+clusters:
+- name: default
+  cluster:
+    server: {server}
+contexts:
+- name: default
+  context:
+    cluster: default
+    user: admin
+current-context: default
+users:
+- name: admin
+  user:
+    token: {token}
+"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn prefixes_names_and_references() {
+        let mut cfg = downloaded("https://1.2.3.4:6443", "abc");
+        prefix_names(&mut cfg, "canine-acme-production");
+
+        assert_eq!(cfg.clusters[0].name, "canine-acme-production");
+        assert_eq!(cfg.users[0].name, "canine-acme-production");
+        assert_eq!(cfg.contexts[0].name, "canine-acme-production");
+        assert_eq!(cfg.contexts[0].context.cluster, "canine-acme-production");
+        assert_eq!(cfg.contexts[0].context.user, "canine-acme-production");
+        assert_eq!(cfg.current_context.as_deref(), Some("canine-acme-production"));
+    }
+
+    #[test]
+    fn merge_keeps_existing_entries() {
+        let mut target = downloaded("https://10.0.0.1:6443", "mine");
+        target.current_context = Some("default".to_string());
+
+        let mut source = downloaded("https://1.2.3.4:6443", "abc");
+        prefix_names(&mut source, "canine-acme-production");
+
+        let report = merge_kubeconfig(&mut target, source, false);
+
+        assert_eq!(target.clusters.len(), 2);
+        assert_eq!(target.users.len(), 2);
+        assert_eq!(target.contexts.len(), 2);
+        assert_eq!(target.clusters[0].cluster.server, "https://10.0.0.1:6443");
+        assert_eq!(target.current_context.as_deref(), Some("default"));
+        assert_eq!(report.added.len(), 3);
+        assert!(report.replaced.is_empty());
+    }
+
+    #[test]
+    fn merge_replaces_entries_with_the_same_name() {
+        let mut target = Kubeconfig::default();
+        let mut first = downloaded("https://1.2.3.4:6443", "old");
+        prefix_names(&mut first, "canine-acme-production");
+        merge_kubeconfig(&mut target, first, false);
+
+        let mut second = downloaded("https://5.6.7.8:6443", "new");
+        prefix_names(&mut second, "canine-acme-production");
+        let report = merge_kubeconfig(&mut target, second, true);
+
+        assert_eq!(target.clusters.len(), 1);
+        assert_eq!(target.clusters[0].cluster.server, "https://5.6.7.8:6443");
+        assert_eq!(target.users[0].user.token.as_deref(), Some("new"));
+        assert_eq!(target.current_context.as_deref(), Some("canine-acme-production"));
+        assert!(report.added.is_empty());
+        assert_eq!(report.replaced.len(), 3);
+    }
+
+    #[test]
+    fn merge_keeps_unknown_top_level_keys() {
+        let mut target = parse_kubeconfig_yaml("apiVersion: v1\nkind: Config\nx-team: platform\n").unwrap();
+        merge_kubeconfig(&mut target, downloaded("https://1.2.3.4:6443", "abc"), false);
+
+        let yaml = kubeconfig_to_yaml(&target).unwrap();
+        assert!(yaml.contains("x-team: platform"));
+    }
+
+    #[test]
+    fn merge_keeps_separate_clusters() {
+        let mut target = Kubeconfig::default();
+        for (cluster, server) in [("production", "https://1.2.3.4:6443"), ("staging", "https://5.6.7.8:6443")] {
+            let mut cfg = downloaded(server, "abc");
+            prefix_names(&mut cfg, &format!("canine-acme-{}", cluster));
+            merge_kubeconfig(&mut target, cfg, false);
+        }
+
+        let names: Vec<_> = target.contexts.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["canine-acme-production", "canine-acme-staging"]);
+        assert_eq!(target.current_context, None);
+    }
+
+    fn codes(findings: &[Finding]) -> Vec<&'static str> {
+        findings.iter().map(|f| f.code).collect()
+    }
+
+    #[test]
+    fn lint_accepts_a_downloaded_kubeconfig() {
+        assert!(lint(&downloaded("https://1.2.3.4:6443", "abc"), None).is_empty());
+    }
+
+    #[test]
+    fn lint_reports_dangling_references() {
+        let mut cfg = downloaded("https://1.2.3.4:6443", "abc");
+        cfg.contexts[0].context.cluster = "missing".to_string();
+        cfg.contexts[0].context.user = "nobody".to_string();
+        cfg.current_context = Some("gone".to_string());
+
+        let findings = lint(&cfg, None);
+        assert_eq!(codes(&findings), ["dangling-cluster", "dangling-user", "dangling-current-context"]);
+        assert_eq!(findings[0].location, "contexts[default].cluster");
+        assert!(findings.iter().all(|f| f.severity == Severity::Error));
+    }
+
+    #[test]
+    fn lint_reports_duplicate_names() {
+        let mut cfg = downloaded("https://1.2.3.4:6443", "abc");
+        cfg.clusters.push(cfg.clusters[0].clone());
+
+        let findings = lint(&cfg, None);
+        assert_eq!(codes(&findings), ["duplicate-name"]);
+        assert_eq!(findings[0].location, "clusters[default]");
+    }
+
+    #[test]
+    fn lint_reports_insecure_cluster_with_ca() {
+        let mut cfg = downloaded("https://1.2.3.4:6443", "abc");
+        cfg.clusters[0].cluster.insecure_skip_tls_verify = Some(true);
+        cfg.clusters[0].cluster.certificate_authority_data = Some("LS0t".to_string());
+
+        assert_eq!(codes(&lint(&cfg, None)), ["insecure-with-ca"]);
+    }
+
+    #[test]
+    fn lint_resolves_files_against_the_kubeconfig_directory() {
+        let dir = std::env::temp_dir().join(format!("canine-lint-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("token"), "abc").unwrap();
+
+        let mut cfg = downloaded("https://1.2.3.4:6443", "abc");
+        cfg.users[0].user.token_file = Some("token".to_string());
+        assert!(lint(&cfg, Some(&dir)).is_empty());
+
+        cfg.clusters[0].cluster.certificate_authority = Some("ca.crt".to_string());
+        let findings = lint(&cfg, Some(&dir));
+        assert_eq!(codes(&findings), ["unreadable-file"]);
+        assert_eq!(findings[0].location, "clusters[default].certificate-authority");
+
+        cfg.clusters[0].cluster.certificate_authority = None;
+        cfg.users[0].user.token_file = Some(".".to_string());
+        let findings = lint(&cfg, Some(&dir));
+        assert_eq!(codes(&findings), ["unreadable-file"]);
+        assert!(findings[0].message.ends_with("is not a file"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
 
 use std::io;
 use std::process::Command;
 
+/// Why [`ensure_kubectl`] failed.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum KubectlError {
+    /// `kubectl` is not on the `PATH`.
     NotFound,
+    /// `kubectl` exists but could not be started.
     NotExecutable(io::Error),
+    /// `kubectl version` failed, with its stderr.
     FailedToRun(String),
 }
 
+impl std::fmt::Display for KubectlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KubectlError::NotFound => write!(f, "kubectl not found"),
+            KubectlError::NotExecutable(e) => write!(f, "kubectl could not be executed: {}", e),
+            KubectlError::FailedToRun(stderr) => write!(f, "kubectl failed: {}", stderr.trim()),
+        }
+    }
+}
+
+impl std::error::Error for KubectlError {}
+
+/// Checks that `kubectl` is installed and runs.
 pub fn ensure_kubectl() -> Result<(), KubectlError> {
     let output = Command::new("kubectl")
         .arg("version")
@@ -0,0 +1,29 @@
+//! Client for the [Canine](https://canine.sh) API, as used by the `canine` CLI.
+//!
+//! ```no_run
+//! use canine::client::CanineClient;
+//! use futures_util::TryStreamExt;
+//!
+//! # async fn run() -> Result<(), canine::client::CanineError> {
+//! let client = CanineClient::builder()
+//!     .host("https://canine.example.com")
+//!     .api_key(std::env::var("CANINE_TOKEN").unwrap())
+//!     .account("acme")
+//!     .build()?;
+//!
+//! for project in client.get_projects().await?.projects {
+//!     println!("{} {}", project.name, project.status);
+//! }
+//! let builds: Vec<_> = client.builds().project("api").stream().try_collect().await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The typed models live in [`client`] next to the client, and [`kubeconfig`]
+//! parses and merges the cluster credentials returned by
+//! [`CanineClient::download_kubeconfig_file`](client::CanineClient::download_kubeconfig_file).
+
+#![warn(missing_docs)]
+
+pub mod client;
+pub mod kubeconfig;
@@ -1,43 +1,97 @@
 mod cli;
-mod client;
 mod commands;
+mod completers;
 mod config;
-mod kubeconfig;
+mod exec;
+mod output;
+mod secrets;
+mod workspace;
 
-use clap::Parser;
+use canine::{client, kubeconfig};
+use clap::{CommandFactory, Parser};
+use clap_complete::CompleteEnv;
 use colored::Colorize;
 
-use cli::{AccountAction, AddOnAction, AuthAction, BuildAction, Cli, ClusterAction, LocalAction, Namespace, ProjectAction};
-use client::{Auth, CanineClient};
-use config::CanineConfig;
+use cli::{AccountAction, AddOnAction, AuthAction, BuildAction, Cli, ClusterAction, ConfigAction, DeploymentAction, EnvAction, KubeconfigAction, LocalAction, Namespace, OneOffAction, ProjectAction};
+use client::{CanineClient, CanineError, ClientOptions};
+use config::{CanineConfig, Profile};
+use workspace::Workspace;
 
-fn build_default_client(config: &CanineConfig) -> CanineClient {
-    CanineClient::new(
-        config
-            .host
-            .clone()
-            .unwrap_or_else(|| CanineConfig::DEFAULT_HOST.to_string()),
-        Auth::ApiKey(config.token.clone().expect("Client is not authenticated")),
-        config.account.clone(),
-    )
-    .unwrap()
+fn build_default_client(
+    credentials: &Profile,
+    workspace: &Workspace,
+    options: ClientOptions,
+) -> Result<CanineClient, Box<dyn std::error::Error>> {
+    workspace.check_host(credentials.host.as_deref().unwrap_or(client::DEFAULT_HOST))?;
+    let token = credentials.token.clone().ok_or(CanineError::NoToken)?;
+    let mut builder = CanineClient::builder().api_key(token).options(options);
+    if let Some(host) = &credentials.host {
+        builder = builder.host(host);
+    }
+    if let Some(account) = &credentials.account {
+        builder = builder.account(account);
+    }
+    Ok(builder.build()?)
 }
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let cli = Cli::parse();
-    let config = CanineConfig::load();
+async fn main() {
+    CompleteEnv::with_factory(Cli::command)
+        .var(commands::completion::COMPLETE_VAR)
+        .complete();
+
+    if let Err(e) = run(Cli::parse()).await {
+        eprintln!("{} {}", "✗".red(), e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    let config = CanineConfig::load(cli.profile.as_deref());
+    let overrides = cli.overrides();
+    let options = cli.client_options();
+    // Flags and environment variables win over .canine.yaml, which wins over the profile.
+    // The host always comes from the profile or the overrides, never from .canine.yaml.
+    let workspace = Workspace::discover();
+    let credentials = config.resolve(&overrides.clone().or(workspace.defaults()));
 
     match cli.namespace {
         Namespace::Auth(cmd) => match cmd.action {
             AuthAction::Login(login) => {
-                commands::auth::handle_login(login).await?;
+                commands::auth::handle_login(&config, overrides, options, login).await?;
             }
             AuthAction::Status => {
-                commands::auth::handle_status(&config).await?;
+                let client = build_default_client(&credentials, &workspace, options)?;
+                commands::auth::handle_status(&client, cli.output).await?;
             }
             AuthAction::Logout => {
-                commands::auth::handle_logout().await?;
+                commands::auth::handle_logout(&config).await?;
+            }
+        },
+
+        Namespace::Config(cmd) => match cmd.action {
+            ConfigAction::UseProfile(name) => {
+                commands::config::handle_use_profile(&name).await?;
+            }
+            ConfigAction::GetProfiles => {
+                commands::config::handle_get_profiles(cli.output).await?;
+            }
+            ConfigAction::DeleteProfile(name) => {
+                commands::config::handle_delete_profile(&name).await?;
+            }
+        },
+
+        Namespace::Completion(cmd) => {
+            commands::completion::handle_completion(&cmd).await?;
+        }
+
+        Namespace::Man(cmd) => {
+            commands::completion::handle_man(&cmd).await?;
+        }
+
+        Namespace::Kubeconfig(cmd) => match cmd.action {
+            KubeconfigAction::Lint(params) => {
+                commands::kubeconfig::handle_lint(&params, cli.output)?;
             }
         },
 
@@ -57,13 +111,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         },
 
         other => {
-            let client = build_default_client(&config);
+            let client = build_default_client(&credentials, &workspace, options)?;
             eprintln!(
-                "{} {}  {} {}",
+                "{} {}  {} {}  {} {}",
+                "Profile:".dimmed(),
+                config.profile.cyan(),
                 "Host:".dimmed(),
                 client.base_url.as_str().cyan(),
                 "Account:".dimmed(),
-                config.account.as_deref().unwrap_or("default").cyan()
+                credentials.account.as_deref().unwrap_or("default").cyan()
             );
 
             match other {
@@ -74,48 +130,109 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     }
                 },
                 Namespace::Projects(cmd) => match cmd.action {
-                    ProjectAction::List => {
-                        commands::project::handle_list(&client).await?;
+                    ProjectAction::List(params) => {
+                        commands::project::handle_list(&client, &params, cli.output).await?;
+                    }
+                    ProjectAction::Create(params) => {
+                        commands::project::handle_create(&client, &params, cli.output).await?;
+                    }
+                    ProjectAction::Update(params) => {
+                        commands::project::handle_update(&client, &params, cli.output).await?;
                     }
-                    ProjectAction::Processes(id) => {
-                        commands::project::handle_processes(&client, &id).await?;
+                    ProjectAction::Delete(params) => {
+                        commands::project::handle_delete(&client, &params).await?;
+                    }
+                    ProjectAction::Processes(params) => {
+                        commands::project::handle_processes(&client, &params, cli.output).await?;
                     }
                     ProjectAction::Run(params) => {
-                        commands::project::handle_run(&config, &client, &params).await?;
+                        let project = workspace.project(&client, params.project.as_deref()).await?;
+                        let code = commands::project::handle_run(&client, &project, &params).await?;
+                        if code != 0 {
+                            std::process::exit(code);
+                        }
+                    }
+                    ProjectAction::Logs(params) => {
+                        let project = workspace.project(&client, params.project.as_deref()).await?;
+                        commands::project::handle_logs(&config, &client, &project, &params).await?;
                     }
                     ProjectAction::Deploy(params) => {
-                        commands::project::handle_deploy(&client, &params).await?;
+                        let project = workspace.project(&client, params.name.as_deref()).await?;
+                        commands::project::handle_deploy(&client, &project, &params, cli.output).await?;
+                    }
+                    ProjectAction::Scale(params) => {
+                        commands::project::handle_scale(&client, &params).await?;
+                    }
+                    ProjectAction::Restart(params) => {
+                        commands::project::handle_restart(&client, &params).await?;
+                    }
+                    ProjectAction::Deployments(DeploymentAction::List(id)) => {
+                        commands::project::handle_deployments(&client, &id, cli.output).await?;
+                    }
+                    ProjectAction::Rollback(params) => {
+                        commands::project::handle_rollback(&client, &params, cli.output).await?;
+                    }
+                    ProjectAction::Env(action) => match action {
+                        EnvAction::List(params) => commands::env::handle_list(&client, &params, cli.output).await?,
+                        EnvAction::Get(params) => commands::env::handle_get(&client, &params, cli.output).await?,
+                        EnvAction::Set(params) => commands::env::handle_set(&client, &params).await?,
+                        EnvAction::Unset(params) => commands::env::handle_unset(&client, &params).await?,
+                        EnvAction::Import(params) => commands::env::handle_import(&client, &params).await?,
+                        EnvAction::Export(params) => commands::env::handle_export(&client, &params).await?,
+                    },
+                    ProjectAction::OneOffs(OneOffAction::List(id)) => {
+                        commands::project::handle_one_offs(&client, &id, cli.output).await?;
+                    }
+                    ProjectAction::OneOffs(OneOffAction::Prune(params)) => {
+                        commands::project::handle_prune_one_offs(&client, &params).await?;
                     }
                 },
+                Namespace::Apply(cmd) => {
+                    commands::apply::handle_apply(&client, &cmd).await?;
+                }
+                Namespace::Init(cmd) => {
+                    commands::init::handle_init(&client, &credentials, &cmd).await?;
+                }
                 Namespace::Builds(cmd) => match cmd.action {
                     BuildAction::List(list) => {
-                        commands::build::handle_list(&client, &list.project).await?;
+                        // Without a project this lists builds across the account, so
+                        // only an explicit .canine.yaml narrows it down.
+                        let project = list.project.clone().or(workspace.project.clone());
+                        commands::build::handle_list(&client, &project, &list, cli.output).await?;
                     }
                     BuildAction::Kill(id) => {
-                        commands::build::handle_kill(&client, &id.build).await?;
+                        commands::build::handle_kill(&client, id.build).await?;
+                    }
+                    BuildAction::Logs(params) => {
+                        commands::build::handle_logs(&client, &params).await?;
                     }
                 }
                 Namespace::Clusters(cmd) => match cmd.action {
-                    ClusterAction::List => {
-                        commands::cluster::handle_list(&client).await?;
+                    ClusterAction::List(params) => {
+                        commands::cluster::handle_list(&client, &params, cli.output).await?;
                     }
                     ClusterAction::Connect(id) => {
                         commands::cluster::handle_connect(&config, &client, &id).await?;
                     }
-                    ClusterAction::DownloadKubeconfig(id) => {
-                        commands::cluster::handle_download_kubeconfig(&config, &client, &id)
+                    ClusterAction::DownloadKubeconfig(params) => {
+                        commands::cluster::handle_download_kubeconfig(&config, &client, &credentials, &params)
                             .await?;
                     }
                 },
                 Namespace::AddOns(cmd) => match cmd.action {
-                    AddOnAction::List => {
-                        commands::add_on::handle_list(&client).await?;
+                    AddOnAction::List(params) => {
+                        commands::add_on::handle_list(&client, &params, cli.output).await?;
                     }
                     AddOnAction::Restart(id) => {
                         commands::add_on::handle_restart(&client, &id).await?;
                     }
                 }
-                Namespace::Auth(_) | Namespace::Local(_) => unreachable!(),
+                Namespace::Auth(_)
+                | Namespace::Config(_)
+                | Namespace::Completion(_)
+                | Namespace::Man(_)
+                | Namespace::Kubeconfig(_)
+                | Namespace::Local(_) => unreachable!(),
             }
         }
     }
@@ -0,0 +1,685 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::future::Future;
+use std::io::{self, IsTerminal};
+use std::time::Duration;
+
+use clap::ValueEnum;
+use colored::Colorize;
+use futures_util::{StreamExt, TryStreamExt};
+use serde::Serialize;
+use strum::VariantNames;
+use tabled::builder::Builder;
+use tabled::{Table, Tabled};
+
+use crate::cli::{ListOptions, PageOptions, WatchOptions};
+use crate::kubeconfig::Finding;
+
+use crate::client::{
+    AddOn, AddOnStatus, Account, Build, BuildStatus, CanineError, Cluster, ClusterStatus, Deployment,
+    EnvironmentVariable, ListRequest, Paginated, Process, ProcessStatus, Project,
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable table
+    #[default]
+    Table,
+    /// Table with the extra columns some lists hide by default
+    Wide,
+    /// Pretty printed JSON
+    Json,
+    /// YAML document
+    Yaml,
+    /// One JSON object per line
+    Ndjson,
+}
+
+/// How a type's fields show up in tables and in the list options.
+pub trait Columns {
+    /// Columns that are only shown with `--output wide`.
+    const WIDE_COLUMNS: &'static [&'static str] = &[];
+    /// Shorter names accepted by `--filter`, `--sort-by` and `--columns`.
+    const ALIASES: &'static [(&'static str, &'static str)] = &[];
+    /// Columns the API can filter on, with the query parameter it expects.
+    const QUERY_PARAMS: &'static [(&'static str, &'static str)] = &[];
+
+    /// What `--sort-by column` orders by, when the table shows something
+    /// meant for people instead, like `4m 05s`.
+    fn sort_value(&self, _column: &str) -> Option<String> {
+        None
+    }
+}
+
+impl Columns for Account {}
+
+impl Columns for Project {
+    const WIDE_COLUMNS: &'static [&'static str] = &["dockerfile_path", "build_context", "autodeploy"];
+    const ALIASES: &'static [(&'static str, &'static str)] = &[("cluster", "cluster_name")];
+}
+
+impl Columns for Cluster {
+    const ALIASES: &'static [(&'static str, &'static str)] = &[("type", "cluster_type")];
+}
+
+impl Columns for Build {
+    const WIDE_COLUMNS: &'static [&'static str] = &["deployment"];
+    const ALIASES: &'static [(&'static str, &'static str)] = &[("project", "project_slug"), ("commit", "commit_sha")];
+    const QUERY_PARAMS: &'static [(&'static str, &'static str)] = &[("project_slug", "project_id")];
+
+    fn sort_value(&self, column: &str) -> Option<String> {
+        (column == "duration").then(|| {
+            self.duration()
+                .map(|duration| duration.num_seconds().to_string())
+                .unwrap_or_default()
+        })
+    }
+}
+
+impl Columns for Deployment {
+    const WIDE_COLUMNS: &'static [&'static str] = &["commit_message"];
+}
+
+impl Columns for Process {}
+
+impl Columns for EnvironmentVariable {}
+
+impl Columns for Finding {
+    const WIDE_COLUMNS: &'static [&'static str] = &["code"];
+}
+
+impl Columns for AddOn {
+    const ALIASES: &'static [(&'static str, &'static str)] = &[("cluster", "cluster_name")];
+}
+
+/// Rows that `--watch` can follow from one refresh to the next.
+pub trait Watched {
+    /// Every status a row can have, to check `--until` against.
+    const STATUSES: &'static [&'static str];
+    /// Identifies the row across refreshes.
+    fn key(&self) -> String;
+    fn status(&self) -> String;
+}
+
+impl Watched for Build {
+    const STATUSES: &'static [&'static str] = BuildStatus::VARIANTS;
+    fn key(&self) -> String {
+        self.id.to_string()
+    }
+    fn status(&self) -> String {
+        self.status.to_string()
+    }
+}
+
+impl Watched for Process {
+    const STATUSES: &'static [&'static str] = ProcessStatus::VARIANTS;
+    fn key(&self) -> String {
+        self.name.clone()
+    }
+    fn status(&self) -> String {
+        self.status.to_string()
+    }
+}
+
+impl Watched for Cluster {
+    const STATUSES: &'static [&'static str] = ClusterStatus::VARIANTS;
+    fn key(&self) -> String {
+        self.id.to_string()
+    }
+    fn status(&self) -> String {
+        self.status.to_string()
+    }
+}
+
+impl Watched for AddOn {
+    const STATUSES: &'static [&'static str] = AddOnStatus::VARIANTS;
+    fn key(&self) -> String {
+        self.id.to_string()
+    }
+    fn status(&self) -> String {
+        self.status.to_string()
+    }
+}
+
+pub fn table<T: Tabled + Columns>(items: &[T], wide: bool) -> Table {
+    render(items, &default_columns::<T>(wide), |_| false)
+}
+
+fn default_columns<T: Tabled + Columns>(wide: bool) -> Vec<usize> {
+    let headers = T::headers();
+    (0..headers.len())
+        .filter(|&i| wide || !T::WIDE_COLUMNS.contains(&headers[i].as_ref()))
+        .collect()
+}
+
+/// The columns picked with `--columns`, in the order given, or the default ones.
+fn visible_columns<T: Tabled + Columns>(wide: bool, columns: &[String]) -> Result<Vec<usize>, String> {
+    if columns.is_empty() {
+        return Ok(default_columns::<T>(wide));
+    }
+    columns.iter().map(|name| column::<T>(name)).collect()
+}
+
+/// Position of the column called `name`, or aliased to it.
+fn column<T: Tabled + Columns>(name: &str) -> Result<usize, String> {
+    let name = resolve::<T>(name);
+    let headers = T::headers();
+    headers.iter().position(|header| header == name).ok_or_else(|| {
+        let known: Vec<_> = headers.iter().map(|header| header.as_ref()).collect();
+        format!("Unknown column `{}`, expected one of {}", name, known.join(", "))
+    })
+}
+
+fn resolve<T: Columns>(name: &str) -> &str {
+    T::ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map_or(name, |(_, column)| column)
+}
+
+/// Query parameters for the `--filter`s the API applies itself. `select`
+/// checks them again, so a server that ignores one still gives the right rows.
+pub fn query<T: Columns>(options: &ListOptions) -> Vec<(String, String)> {
+    options
+        .filter
+        .iter()
+        .filter_map(|(name, value)| {
+            let name = resolve::<T>(name);
+            T::QUERY_PARAMS
+                .iter()
+                .find(|(column, _)| *column == name)
+                .map(|(_, param)| (param.to_string(), value.clone()))
+        })
+        .collect()
+}
+
+/// Applies `--filter`, `--sort-by` and `--limit`.
+pub fn select<T: Tabled + Columns>(items: Vec<T>, options: &ListOptions) -> Result<Vec<T>, String> {
+    let filters = options
+        .filter
+        .iter()
+        .map(|(name, value)| Ok((column::<T>(name)?, value)))
+        .collect::<Result<Vec<_>, String>>()?;
+    let mut items: Vec<T> = items
+        .into_iter()
+        .filter(|item| {
+            let fields = item.fields();
+            filters.iter().all(|(i, value)| loosely_equal(&fields[*i], value))
+        })
+        .collect();
+
+    if let Some(sort_by) = &options.sort_by {
+        let i = column::<T>(sort_by)?;
+        let header = T::headers()[i].to_string();
+        let mut keyed: Vec<_> = items
+            .into_iter()
+            .map(|item| (item.sort_value(&header).unwrap_or_else(|| item.fields()[i].to_string()), item))
+            .collect();
+        let numeric = keyed
+            .iter()
+            .filter(|(value, _)| !value.is_empty())
+            .all(|(value, _)| value.parse::<f64>().is_ok());
+        keyed.sort_by(|(a, _), (b, _)| if numeric { compare_numbers(a, b) } else { a.cmp(b) });
+        if options.desc {
+            keyed.reverse();
+        }
+        items = keyed.into_iter().map(|(_, item)| item).collect();
+    }
+    if let Some(limit) = options.limit {
+        items.truncate(limit);
+    }
+    Ok(items)
+}
+
+/// Orders a column in which every value is a number, with blanks first.
+/// Mixing numeric and text order would not be a total order, so columns
+/// holding anything else (such as short SHAs) sort alphabetically instead.
+fn compare_numbers(a: &str, b: &str) -> Ordering {
+    match (a.parse::<f64>().ok(), b.parse::<f64>().ok()) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        (a, b) => a.is_some().cmp(&b.is_some()),
+    }
+}
+
+/// Compares ignoring case and punctuation, so `in_progress` matches `InProgress`.
+fn loosely_equal(a: &str, b: &str) -> bool {
+    fn normalize(value: &str) -> String {
+        value
+            .chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect()
+    }
+    normalize(a) == normalize(b)
+}
+
+/// Renders the `visible` columns, with the rows picked by `highlight` drawn in color.
+fn render<T: Tabled>(items: &[T], visible: &[usize], highlight: impl Fn(&T) -> bool) -> Table {
+    let headers = T::headers();
+    let mut builder = Builder::new();
+    builder.push_record(visible.iter().map(|&i| headers[i].to_string()));
+    for item in items {
+        let fields = item.fields();
+        let highlight = highlight(item);
+        builder.push_record(visible.iter().map(|&i| {
+            if highlight {
+                fields[i].yellow().bold().to_string()
+            } else {
+                fields[i].to_string()
+            }
+        }));
+    }
+    builder.build()
+}
+
+/// Fetches the page asked for with `--page`, or every page with `--all`.
+/// `--limit` stops `--all` early unless rows still have to be filtered or
+/// sorted, which needs all of them.
+pub async fn fetch<'a, R: Paginated + 'a>(
+    pages: &PageOptions,
+    list: &ListOptions,
+    request: ListRequest<'a, R>,
+) -> Result<Vec<R::Item>, CanineError> {
+    let request = match pages.per_page {
+        Some(per_page) => request.per_page(per_page),
+        None => request,
+    };
+    if pages.all {
+        let stream = request.stream();
+        return match list.limit.filter(|_| list.filter.is_empty() && list.sort_by.is_none()) {
+            Some(limit) => stream.take(limit).try_collect().await,
+            None => stream.try_collect().await,
+        };
+    }
+    let request = match pages.page {
+        Some(page) => request.page(page),
+        None => request,
+    };
+    let page = request.send().await?;
+    if page.has_next() {
+        let position = page
+            .meta
+            .as_ref()
+            .and_then(|meta| Some(format!("Showing page {} of {}. ", meta.current_page?, meta.total_pages?)))
+            .unwrap_or_default();
+        eprintln!("{} {}There are more results, pass --all or --page to see them", "!".yellow(), position);
+    }
+    Ok(page.items)
+}
+
+/// Prints the rows of a collection picked by the list options. `--columns`
+/// only changes tables; other formats keep every field.
+pub fn print_selected<T>(items: Vec<T>, format: OutputFormat, options: &ListOptions) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: Serialize + Tabled + Columns,
+{
+    let items = select(items, options)?;
+    match format {
+        OutputFormat::Table | OutputFormat::Wide => {
+            let visible = visible_columns::<T>(format == OutputFormat::Wide, &options.columns)?;
+            println!("{}", render(&items, &visible, |_| false));
+            Ok(())
+        }
+        _ => print_list(&items, format),
+    }
+}
+
+/// Prints a collection in the requested format.
+pub fn print_list<T>(items: &[T], format: OutputFormat) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: Serialize + Tabled + Columns,
+{
+    match format {
+        OutputFormat::Table => println!("{}", table(items, false)),
+        OutputFormat::Wide => println!("{}", table(items, true)),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(items)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(items)?),
+        OutputFormat::Ndjson => {
+            for item in items {
+                println!("{}", serde_json::to_string(item)?);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Refreshes a list until interrupted, or until every row reaches one of the
+/// `--until` statuses. Tables are redrawn in place with changed rows
+/// highlighted; other formats print each refresh in turn. A failed refresh is
+/// reported and tried again after the interval.
+pub async fn watch<T, F, Fut>(
+    options: &WatchOptions,
+    list: &ListOptions,
+    format: OutputFormat,
+    mut fetch: F,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    T: Serialize + Tabled + Columns + Watched,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<Vec<T>, CanineError>>,
+{
+    check_statuses::<T>(&options.until)?;
+    let interval = Duration::from_secs(options.interval);
+    let redraw = io::stdout().is_terminal();
+    let visible = visible_columns::<T>(format == OutputFormat::Wide, &list.columns)?;
+    let mut previous: Option<HashMap<String, String>> = None;
+    loop {
+        let items = match fetch().await {
+            Ok(items) => select(items, list)?,
+            Err(e) => {
+                eprintln!("{} {}, retrying in {}s", "!".yellow(), e, options.interval);
+                tokio::time::sleep(interval).await;
+                continue;
+            }
+        };
+        match format {
+            OutputFormat::Table | OutputFormat::Wide => {
+                let changed = |item: &T| {
+                    previous
+                        .as_ref()
+                        .is_some_and(|previous| previous.get(&item.key()) != Some(&item.status()))
+                };
+                let table = render(&items, &visible, changed);
+                if redraw {
+                    crossterm::execute!(
+                        io::stdout(),
+                        crossterm::terminal::Clear(crossterm::terminal::ClearType::All),
+                        crossterm::cursor::MoveTo(0, 0)
+                    )?;
+                }
+                let now = chrono::Local::now().format("%H:%M:%S");
+                println!("{}", format!("Every {}s, last refreshed {}", options.interval, now).dimmed());
+                println!("{}", table);
+            }
+            _ => print_list(&items, format)?,
+        }
+
+        if !options.until.is_empty() && settled(&items, &options.until) {
+            return Ok(());
+        }
+        previous = Some(items.iter().map(|item| (item.key(), item.status())).collect());
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Rejects `--until` values no row could ever have, which would watch forever.
+fn check_statuses<T: Watched>(until: &[String]) -> Result<(), String> {
+    match until
+        .iter()
+        .find(|status| !T::STATUSES.iter().any(|known| loosely_equal(known, status)))
+    {
+        Some(status) => Err(format!(
+            "Unknown status `{}` for --until, expected one of: {}",
+            status,
+            T::STATUSES.join(", ")
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Whether every row has one of `statuses`. An empty list has not settled yet.
+fn settled<T: Watched>(items: &[T], statuses: &[String]) -> bool {
+    !items.is_empty()
+        && items
+            .iter()
+            .all(|item| statuses.iter().any(|status| loosely_equal(&item.status(), status)))
+}
+
+/// Asks the user to type `expected` to go ahead with a destructive action.
+/// Fails when stdin is not a terminal, so scripts have to pass `--yes`.
+pub fn confirm(prompt: &str, expected: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    if !io::stdin().is_terminal() {
+        return Err("refusing to continue without confirmation, pass --yes".into());
+    }
+    eprint!("{} Type {} to confirm: ", prompt, expected.bold());
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(answer.trim() == expected)
+}
+
+/// Prints a single object. Table formats fall back to `human`, which renders
+/// the object the way the command always has.
+pub fn print_item<T: Serialize>(
+    item: &T,
+    format: OutputFormat,
+    human: impl FnOnce(&T),
+) -> Result<(), Box<dyn std::error::Error>> {
+    match format {
+        OutputFormat::Table | OutputFormat::Wide => human(item),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(item)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(item)?),
+        OutputFormat::Ndjson => println!("{}", serde_json::to_string(item)?),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn wide_columns_are_hidden_by_default() {
+        let deployments: Vec<Deployment> = vec![
+            serde_json::from_value(json!({
+                "id": 1,
+                "build_id": 7,
+                "status": "completed",
+                "commit_sha": "abc1234",
+                "commit_message": "Fix login",
+                "created_at": "2026-10-17T12:00:00Z",
+            }))
+            .unwrap(),
+        ];
+
+        let narrow = table(&deployments, false).to_string();
+        assert!(narrow.contains("commit_sha"));
+        assert!(!narrow.contains("commit_message"));
+
+        let wide = table(&deployments, true).to_string();
+        assert!(wide.contains("commit_message"));
+
+        let add_ons = vec![add_on(1, AddOnStatus::Installed)];
+        assert!(table(&add_ons, false).to_string().contains("cluster_id"));
+    }
+
+    #[test]
+    fn wide_shows_build_settings_of_projects() {
+        let projects: Vec<Project> = vec![
+            serde_json::from_value(json!({
+                "id": 1,
+                "name": "web",
+                "namespace": "web",
+                "repository_url": "https://github.com/acme/web",
+                "branch": "main",
+                "status": "deployed",
+                "cluster_id": 7,
+                "cluster_name": "production",
+                "dockerfile_path": "docker/Dockerfile.web",
+                "autodeploy": true,
+            }))
+            .unwrap(),
+        ];
+
+        let narrow = table(&projects, false).to_string();
+        assert!(narrow.contains("cluster_name"));
+        assert!(!narrow.contains("dockerfile_path"));
+
+        let wide = table(&projects, true).to_string();
+        assert!(wide.contains("docker/Dockerfile.web"));
+        assert!(wide.contains("build_context"));
+        assert!(wide.contains("true"));
+    }
+
+    fn add_on(id: i32, status: AddOnStatus) -> AddOn {
+        named_add_on(id, &format!("redis-{}", id), status)
+    }
+
+    fn named_add_on(id: i32, name: &str, status: AddOnStatus) -> AddOn {
+        serde_json::from_value(json!({
+            "id": id,
+            "name": name,
+            "status": status,
+            "cluster_id": 7,
+            "cluster_name": if id % 2 == 0 { "staging" } else { "production" },
+        }))
+        .unwrap()
+    }
+
+    fn list_options(args: &[&str]) -> ListOptions {
+        use clap::Parser;
+        #[derive(Parser)]
+        struct Command {
+            #[command(flatten)]
+            list: ListOptions,
+        }
+        Command::parse_from(std::iter::once("list").chain(args.iter().copied())).list
+    }
+
+    #[test]
+    fn selects_filters_sorts_and_limits_rows() {
+        let add_ons = || (1..=5).map(|id| add_on(id, AddOnStatus::Installed)).collect::<Vec<_>>();
+        let ids = |items: Vec<AddOn>| items.iter().map(|a| a.id).collect::<Vec<_>>();
+
+        let options = list_options(&["--filter", "cluster=production,status=installed", "--sort-by", "id", "--desc"]);
+        assert_eq!(ids(select(add_ons(), &options).unwrap()), [5, 3, 1]);
+
+        let options = list_options(&["--sort-by", "cluster_name", "--limit", "2"]);
+        assert_eq!(ids(select(add_ons(), &options).unwrap()), [1, 3]);
+
+        let options = list_options(&["--filter", "colour=red"]);
+        assert!(select(add_ons(), &options).unwrap_err().contains("Unknown column `colour`"));
+    }
+
+    #[test]
+    fn sorts_mixed_columns_alphabetically() {
+        let shas = ["1234567", "1e4a9c0", "abcdef0", "99", "1e3", "0f00000", "10"];
+        let add_ons = || {
+            shas.iter()
+                .enumerate()
+                .map(|(id, sha)| named_add_on(id as i32, sha, AddOnStatus::Installed))
+                .collect::<Vec<_>>()
+        };
+        let names = |items: Vec<AddOn>| items.into_iter().map(|a| a.name).collect::<Vec<_>>();
+
+        let options = list_options(&["--sort-by", "name"]);
+        assert_eq!(
+            names(select(add_ons(), &options).unwrap()),
+            ["0f00000", "10", "1234567", "1e3", "1e4a9c0", "99", "abcdef0"]
+        );
+
+        let numbers = ["10", "", "9", "1e3"]
+            .iter()
+            .enumerate()
+            .map(|(id, n)| named_add_on(id as i32, n, AddOnStatus::Installed))
+            .collect();
+        assert_eq!(names(select(numbers, &options).unwrap()), ["", "9", "10", "1e3"]);
+    }
+
+    #[test]
+    fn sorts_durations_by_length() {
+        let build = |id: i32, seconds: i64| -> Build {
+            let started_at = chrono::Utc::now();
+            serde_json::from_value(json!({
+                "id": id,
+                "commit_sha": "abc1234",
+                "commit_message": "Fix",
+                "project_id": 1,
+                "project_slug": "api",
+                "status": "completed",
+                "started_at": started_at,
+                "finished_at": started_at + chrono::Duration::seconds(seconds),
+            }))
+            .unwrap()
+        };
+        let builds = vec![build(1, 245), build(2, 9), build(3, 3700)];
+
+        let options = list_options(&["--sort-by", "duration"]);
+        let ids: Vec<_> = select(builds, &options).unwrap().iter().map(|b| b.id).collect();
+        assert_eq!(ids, [2, 1, 3]);
+    }
+
+    #[tokio::test]
+    async fn limit_stops_fetching_pages() {
+        use clap::Parser;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        #[derive(Parser)]
+        struct Command {
+            #[command(flatten)]
+            pages: PageOptions,
+            #[command(flatten)]
+            list: ListOptions,
+        }
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/add_ons"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "add_ons": [
+                    { "id": 1, "name": "redis", "status": "installed", "cluster_id": 7, "cluster_name": "production" },
+                    { "id": 2, "name": "redis", "status": "installed", "cluster_id": 7, "cluster_name": "production" },
+                ],
+                "meta": { "current_page": 1, "total_pages": 5 },
+            })))
+            .mount(&server)
+            .await;
+        let client = crate::commands::mock_client(&server);
+
+        let command = Command::parse_from(["list", "--all", "--limit", "2"]);
+        let add_ons = fetch(&command.pages, &command.list, client.add_ons()).await.unwrap();
+        assert_eq!(add_ons.len(), 2);
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn picks_columns_and_server_side_filters() {
+        assert_eq!(visible_columns::<AddOn>(false, &["status".into(), "id".into()]).unwrap(), [2, 0]);
+
+        let options = list_options(&["--filter", "project=api,status=failed"]);
+        assert_eq!(query::<Build>(&options), [("project_id".to_string(), "api".to_string())]);
+        assert!(query::<AddOn>(&options).is_empty());
+    }
+
+    #[test]
+    fn settles_once_every_row_reaches_a_status() {
+        let until = vec!["installed".to_string(), "failed".to_string()];
+
+        assert!(!settled::<AddOn>(&[], &until));
+        assert!(!settled(&[add_on(1, AddOnStatus::Installed), add_on(2, AddOnStatus::Installing)], &until));
+        assert!(settled(&[add_on(1, AddOnStatus::Installed), add_on(2, AddOnStatus::Failed)], &until));
+        assert!(settled(&[add_on(1, AddOnStatus::Installing)], &["INSTALLING".to_string()]));
+    }
+
+    fn watch_options(until: &[&str]) -> WatchOptions {
+        WatchOptions { watch: true, interval: 0, until: until.iter().map(|s| s.to_string()).collect() }
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_until_statuses() {
+        let err = watch(&watch_options(&["in_progress", "done"]), &list_options(&[]), OutputFormat::Json, || async {
+            Ok(Vec::<Build>::new())
+        })
+        .await
+        .unwrap_err();
+
+        assert!(err.to_string().starts_with("Unknown status `done` for --until, expected one of: InProgress,"));
+    }
+
+    #[tokio::test]
+    async fn keeps_watching_after_a_failed_refresh() {
+        let mut refreshes = 0;
+        watch(&watch_options(&["installed"]), &list_options(&[]), OutputFormat::Json, || {
+            refreshes += 1;
+            let result = match refreshes {
+                1 => Err(CanineError::PaginationLoop),
+                _ => Ok(vec![add_on(1, AddOnStatus::Installed)]),
+            };
+            async { result }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(refreshes, 2);
+    }
+}
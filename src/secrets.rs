@@ -0,0 +1,340 @@
+//! Storage for API tokens.
+//!
+//! Tokens live in the platform secret store (Secret Service on Linux, Keychain on
+//! macOS, Credential Manager on Windows). Machines without one, such as headless
+//! servers, fall back to an encrypted file under `~/.k9`. The config file only
+//! keeps a reference like `keyring:default` or `file:default`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use thiserror::Error;
+
+const KEYRING_SERVICE: &str = "canine";
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug, Error)]
+pub enum SecretError {
+    #[error("keyring error: {0}")]
+    Keyring(#[from] keyring::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("stored credentials could not be decrypted")]
+    Decrypt,
+    #[error("unknown secret reference: {0}")]
+    UnknownReference(String),
+}
+
+pub trait SecretStore {
+    fn get(&self, name: &str) -> Result<Option<String>, SecretError>;
+    fn set(&self, name: &str, secret: &str) -> Result<(), SecretError>;
+    fn delete(&self, name: &str) -> Result<(), SecretError>;
+}
+
+/// The platform secret store.
+pub struct KeyringStore;
+
+impl KeyringStore {
+    /// keyring's Secret Service backend blocks on its own runtime, so calls are
+    /// made from a scoped thread to stay clear of the tokio runtime.
+    fn with_entry<T: Send>(
+        name: &str,
+        f: impl FnOnce(&keyring::Entry) -> keyring::Result<T> + Send,
+    ) -> Result<T, SecretError> {
+        std::thread::scope(|scope| {
+            scope
+                .spawn(|| f(&keyring::Entry::new(KEYRING_SERVICE, name)?))
+                .join()
+                .expect("keyring thread panicked")
+        })
+        .map_err(SecretError::from)
+    }
+}
+
+impl SecretStore for KeyringStore {
+    fn get(&self, name: &str) -> Result<Option<String>, SecretError> {
+        match Self::with_entry(name, |entry| entry.get_password()) {
+            Ok(secret) => Ok(Some(secret)),
+            Err(SecretError::Keyring(keyring::Error::NoEntry)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn set(&self, name: &str, secret: &str) -> Result<(), SecretError> {
+        Self::with_entry(name, |entry| entry.set_password(secret))
+    }
+
+    fn delete(&self, name: &str) -> Result<(), SecretError> {
+        match Self::with_entry(name, |entry| entry.delete_credential()) {
+            Err(SecretError::Keyring(keyring::Error::NoEntry)) => Ok(()),
+            other => other,
+        }
+    }
+}
+
+/// Secrets encrypted with a random key kept next to them. This keeps tokens out
+/// of plain sight in backups and dotfile repos; it does not protect against
+/// someone who can already read the user's home directory.
+pub struct EncryptedFileStore {
+    dir: PathBuf,
+}
+
+impl EncryptedFileStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn secrets_path(&self) -> PathBuf {
+        self.dir.join("credentials.enc")
+    }
+
+    fn key_path(&self) -> PathBuf {
+        self.dir.join("credentials.key")
+    }
+
+    fn cipher(&self) -> Result<ChaCha20Poly1305, SecretError> {
+        let key = match fs::read(self.key_path()) {
+            Ok(key) if key.len() == 32 => key,
+            Ok(_) => return Err(SecretError::Decrypt),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+                write_private(&self.key_path(), &key)?;
+                key.to_vec()
+            }
+            Err(e) => return Err(e.into()),
+        };
+        Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+    }
+
+    fn read_all(&self) -> Result<BTreeMap<String, String>, SecretError> {
+        match fs::read_to_string(self.secrets_path()) {
+            Ok(contents) => Ok(serde_json::from_str(&contents)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn write_all(&self, secrets: &BTreeMap<String, String>) -> Result<(), SecretError> {
+        let json = serde_json::to_string_pretty(secrets)?;
+        write_private(&self.secrets_path(), json.as_bytes())?;
+        Ok(())
+    }
+}
+
+impl SecretStore for EncryptedFileStore {
+    fn get(&self, name: &str) -> Result<Option<String>, SecretError> {
+        let Some(encoded) = self.read_all()?.remove(name) else {
+            return Ok(None);
+        };
+        let bytes = BASE64.decode(encoded).map_err(|_| SecretError::Decrypt)?;
+        if bytes.len() < NONCE_LEN {
+            return Err(SecretError::Decrypt);
+        }
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher()?
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| SecretError::Decrypt)?;
+        String::from_utf8(plaintext)
+            .map(Some)
+            .map_err(|_| SecretError::Decrypt)
+    }
+
+    fn set(&self, name: &str, secret: &str) -> Result<(), SecretError> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher()?
+            .encrypt(&nonce, secret.as_bytes())
+            .map_err(|_| SecretError::Decrypt)?;
+
+        let mut bytes = nonce.to_vec();
+        bytes.extend(ciphertext);
+
+        let mut secrets = self.read_all()?;
+        secrets.insert(name.to_string(), BASE64.encode(bytes));
+        self.write_all(&secrets)
+    }
+
+    fn delete(&self, name: &str) -> Result<(), SecretError> {
+        let mut secrets = self.read_all()?;
+        if secrets.remove(name).is_some() {
+            self.write_all(&secrets)?;
+        }
+        Ok(())
+    }
+}
+
+/// The keyring with the encrypted file as fallback.
+pub struct Secrets {
+    keyring: Box<dyn SecretStore>,
+    file: Box<dyn SecretStore>,
+}
+
+impl Secrets {
+    pub fn new(keyring: Box<dyn SecretStore>, file: Box<dyn SecretStore>) -> Self {
+        Self { keyring, file }
+    }
+
+    pub fn system(dir: &Path) -> Self {
+        Self::new(Box::new(KeyringStore), Box::new(EncryptedFileStore::new(dir)))
+    }
+
+    /// Stores `secret` and returns the reference to keep in the config file.
+    pub fn store(&self, name: &str, secret: &str) -> Result<String, SecretError> {
+        match self.keyring.set(name, secret) {
+            Ok(()) => Ok(format!("keyring:{}", name)),
+            Err(_) => {
+                self.file.set(name, secret)?;
+                Ok(format!("file:{}", name))
+            }
+        }
+    }
+
+    pub fn lookup(&self, reference: &str) -> Result<Option<String>, SecretError> {
+        let (store, name) = self.resolve(reference)?;
+        store.get(name)
+    }
+
+    pub fn remove(&self, reference: &str) -> Result<(), SecretError> {
+        let (store, name) = self.resolve(reference)?;
+        store.delete(name)
+    }
+
+    fn resolve<'a>(&self, reference: &'a str) -> Result<(&dyn SecretStore, &'a str), SecretError> {
+        match reference.split_once(':') {
+            Some(("keyring", name)) => Ok((self.keyring.as_ref(), name)),
+            Some(("file", name)) => Ok((self.file.as_ref(), name)),
+            _ => Err(SecretError::UnknownReference(reference.to_string())),
+        }
+    }
+}
+
+/// Writes `contents` to `path`, readable by the current user only.
+pub fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        if path.exists() {
+            fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+        }
+    }
+
+    let mut file = options.open(path)?;
+    file.write_all(contents)
+}
+
+#[cfg(test)]
+pub mod mock {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    /// In-memory stand-in for the platform keyring.
+    #[derive(Default)]
+    pub struct MemoryStore {
+        pub entries: Mutex<HashMap<String, String>>,
+    }
+
+    impl SecretStore for MemoryStore {
+        fn get(&self, name: &str) -> Result<Option<String>, SecretError> {
+            Ok(self.entries.lock().unwrap().get(name).cloned())
+        }
+
+        fn set(&self, name: &str, secret: &str) -> Result<(), SecretError> {
+            self.entries
+                .lock()
+                .unwrap()
+                .insert(name.to_string(), secret.to_string());
+            Ok(())
+        }
+
+        fn delete(&self, name: &str) -> Result<(), SecretError> {
+            self.entries.lock().unwrap().remove(name);
+            Ok(())
+        }
+    }
+
+    /// A keyring that is never reachable, like on a headless server.
+    pub struct UnavailableStore;
+
+    impl SecretStore for UnavailableStore {
+        fn get(&self, _name: &str) -> Result<Option<String>, SecretError> {
+            Err(keyring::Error::NoStorageAccess(std::io::Error::other("unavailable").into()).into())
+        }
+
+        fn set(&self, name: &str, _secret: &str) -> Result<(), SecretError> {
+            self.get(name).map(|_| ())
+        }
+
+        fn delete(&self, name: &str) -> Result<(), SecretError> {
+            self.get(name).map(|_| ())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::{MemoryStore, UnavailableStore};
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("canine-secrets-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn prefers_keyring() {
+        let dir = temp_dir("keyring");
+        let secrets = Secrets::new(Box::<MemoryStore>::default(), Box::new(EncryptedFileStore::new(&dir)));
+
+        let reference = secrets.store("default", "token-123").unwrap();
+        assert_eq!(reference, "keyring:default");
+        assert_eq!(secrets.lookup(&reference).unwrap().as_deref(), Some("token-123"));
+        assert!(!dir.join("credentials.enc").exists());
+
+        secrets.remove(&reference).unwrap();
+        assert_eq!(secrets.lookup(&reference).unwrap(), None);
+    }
+
+    #[test]
+    fn falls_back_to_encrypted_file() {
+        let dir = temp_dir("file");
+        let secrets = Secrets::new(Box::new(UnavailableStore), Box::new(EncryptedFileStore::new(&dir)));
+
+        let reference = secrets.store("default", "token-123").unwrap();
+        assert_eq!(reference, "file:default");
+        assert_eq!(secrets.lookup(&reference).unwrap().as_deref(), Some("token-123"));
+
+        let on_disk = fs::read_to_string(dir.join("credentials.enc")).unwrap();
+        assert!(!on_disk.contains("token-123"));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            for file in ["credentials.enc", "credentials.key"] {
+                let mode = fs::metadata(dir.join(file)).unwrap().permissions().mode();
+                assert_eq!(mode & 0o777, 0o600);
+            }
+        }
+
+        secrets.remove(&reference).unwrap();
+        assert_eq!(secrets.lookup(&reference).unwrap(), None);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
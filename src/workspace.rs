@@ -0,0 +1,216 @@
+//! Per-repository defaults kept in a `.canine.yaml`, found by walking up from
+//! the current directory the way git finds `.git`.
+//!
+//! ```yaml
+//! project: api
+//! account: acme
+//! host: https://canine.example.com
+//! ```
+//!
+//! The file comes with whatever repository was cloned, so it never decides
+//! where a token is sent: `host` only has to match the host of the profile in
+//! use, and commands refuse to run when it does not.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use colored::Colorize;
+use futures_util::TryStreamExt;
+use serde::{Deserialize, Serialize};
+
+use crate::client::CanineClient;
+use crate::config::Profile;
+
+pub const FILE_NAME: &str = ".canine.yaml";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Workspace {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+
+    /// Where the file was found.
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+}
+
+impl Workspace {
+    /// Looks for `.canine.yaml` in the current directory and its parents. A file
+    /// that cannot be parsed is reported and ignored.
+    pub fn discover() -> Self {
+        let Ok(dir) = std::env::current_dir() else {
+            return Self::default();
+        };
+        match Self::find(&dir) {
+            Ok(workspace) => workspace.unwrap_or_default(),
+            Err(e) => {
+                eprintln!("{} {}", "!".yellow(), e);
+                Self::default()
+            }
+        }
+    }
+
+    fn find(start: &Path) -> Result<Option<Self>, String> {
+        for dir in start.ancestors() {
+            let path = dir.join(FILE_NAME);
+            let Ok(contents) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            let mut workspace: Workspace = serde_yaml::from_str(&contents)
+                .map_err(|e| format!("Ignoring {}: {}", path.display(), e))?;
+            workspace.path = Some(path);
+            return Ok(Some(workspace));
+        }
+        Ok(None)
+    }
+
+    /// Account to use when neither a flag nor an environment variable sets one.
+    pub fn defaults(&self) -> Profile {
+        Profile {
+            account: self.account.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Fails when the file names a host other than `host`, the one the
+    /// profile's token belongs to.
+    pub fn check_host(&self, host: &str) -> Result<(), String> {
+        let Some(expected) = &self.host else {
+            return Ok(());
+        };
+        if same_host(expected, host) {
+            return Ok(());
+        }
+        Err(format!(
+            "{} expects host {}, but the token in use is for {}. Pick a matching profile with --profile",
+            self.path.as_deref().unwrap_or(Path::new(FILE_NAME)).display(),
+            expected,
+            host
+        ))
+    }
+
+    /// The project a command applies to: `explicit` when given, then the one
+    /// named in `.canine.yaml`, then the project built from this repository's
+    /// git remote.
+    pub async fn project(
+        &self,
+        client: &CanineClient,
+        explicit: Option<&str>,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        if let Some(project) = explicit.or(self.project.as_deref()) {
+            return Ok(project.to_string());
+        }
+        let Some(remote) = git_remote() else {
+            return Err(format!(
+                "No project given. Pass --project, or run `canine init` to create a {}",
+                FILE_NAME
+            )
+            .into());
+        };
+
+        let projects: Vec<_> = client.projects().stream().try_collect().await?;
+        let matches: Vec<_> = projects
+            .iter()
+            .filter(|p| same_repository(&p.repository_url, &remote))
+            .map(|p| p.name.as_str())
+            .collect();
+        match matches.as_slice() {
+            [project] => Ok(project.to_string()),
+            [] => Err(format!(
+                "No project is built from {}. Pass --project, or run `canine init` to create a {}",
+                remote, FILE_NAME
+            )
+            .into()),
+            several => Err(format!(
+                "Several projects are built from {} ({}), pass --project to pick one",
+                remote,
+                several.join(", ")
+            )
+            .into()),
+        }
+    }
+}
+
+/// URL of the `origin` remote of the repository in the current directory.
+pub fn git_remote() -> Option<String> {
+    let output = Command::new("git")
+        .args(["remote", "get-url", "origin"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let url = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!url.is_empty()).then_some(url)
+}
+
+fn same_host(a: &str, b: &str) -> bool {
+    match (url::Url::parse(a), url::Url::parse(b)) {
+        (Ok(a), Ok(b)) => a.origin() == b.origin(),
+        _ => a.trim_end_matches('/') == b.trim_end_matches('/'),
+    }
+}
+
+/// Compares repository URLs regardless of protocol, credentials and `.git`
+/// suffix, so `git@github.com:acme/api.git` matches `https://github.com/acme/api`.
+pub fn same_repository(a: &str, b: &str) -> bool {
+    fn normalize(url: &str) -> String {
+        let url = url.trim().trim_end_matches('/');
+        let url = url.strip_suffix(".git").unwrap_or(url);
+        let url = url.split_once("://").map_or(url, |(_, rest)| rest);
+        // scp-like syntax: user@host:path
+        let url = url.split_once('@').map_or(url, |(_, rest)| rest);
+        url.replacen(':', "/", 1).to_lowercase()
+    }
+    normalize(a) == normalize(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_repository_urls_across_protocols() {
+        let https = "https://github.com/Acme/api";
+        assert!(same_repository(https, "git@github.com:acme/api.git"));
+        assert!(same_repository(https, "ssh://git@github.com/acme/api.git"));
+        assert!(same_repository(https, "https://token@github.com/acme/api.git/"));
+        assert!(!same_repository(https, "git@github.com:acme/web.git"));
+    }
+
+    #[test]
+    fn rejects_host_other_than_the_profiles() {
+        let workspace = Workspace {
+            host: Some("https://evil.example.com".to_string()),
+            ..Default::default()
+        };
+        assert!(workspace.check_host("https://canine.sh").is_err());
+        assert!(workspace.check_host("https://evil.example.com/").is_ok());
+        assert!(Workspace::default().check_host("https://canine.sh").is_ok());
+    }
+
+    #[test]
+    fn finds_file_in_parent_directories() {
+        let root = std::env::temp_dir().join(format!("canine-workspace-{}", std::process::id()));
+        let nested = root.join("app/models");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(root.join(FILE_NAME), "project: api\naccount: acme\n").unwrap();
+
+        let workspace = Workspace::find(&nested).unwrap().unwrap();
+        assert_eq!(workspace.project.as_deref(), Some("api"));
+        assert_eq!(workspace.defaults().account.as_deref(), Some("acme"));
+        assert_eq!(workspace.path, Some(root.join(FILE_NAME)));
+
+        std::fs::write(root.join(FILE_NAME), "host: https://evil.example.com\n").unwrap();
+        let workspace = Workspace::find(&nested).unwrap().unwrap();
+        assert_eq!(workspace.defaults().host, None);
+
+        std::fs::write(root.join(FILE_NAME), "projcet: api\n").unwrap();
+        assert!(Workspace::find(&nested).is_err());
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}